@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use lsx::sha256::{self, Backend};
+use std::hint::black_box;
+
+fn backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("1 MiB hash");
+    group.throughput(Throughput::Bytes(1 << 20));
+    let data = vec![0x42u8; 1 << 20];
+    for &(name, backend) in &[("scalar", Backend::Scalar),
+                              ("auto", Backend::Auto)] {
+        group.bench_function(name, |b| {
+            sha256::set_backend(backend);
+            b.iter(|| sha256::hash(black_box(&data[..])))
+        });
+    }
+    sha256::set_backend(Backend::Auto);
+    group.finish();
+}
+
+#[cfg(feature="rayon")]
+fn many_messages(c: &mut Criterion) {
+    let mut group = c.benchmark_group("10000 x 1 KiB messages");
+    group.throughput(Throughput::Bytes(10000 << 10));
+    let data = vec![0x42u8; 10000 << 10];
+    let inputs: Vec<&[u8]> = data.chunks(1 << 10).collect();
+    group.bench_function("serial", |b| {
+        b.iter(|| black_box(&inputs).iter().map(|x| sha256::hash(x))
+               .collect::<Vec<_>>())
+    });
+    group.bench_function("hash_many", |b| {
+        b.iter(|| sha256::hash_many(black_box(&inputs)))
+    });
+    group.finish();
+}
+
+#[cfg(not(feature="rayon"))]
+fn many_messages(_: &mut Criterion) {}
+
+criterion_group!(benches, backends, many_messages);
+criterion_main!(benches);
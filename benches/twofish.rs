@@ -0,0 +1,144 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use lsx::twofish::Twofish;
+use std::convert::TryInto;
+use std::hint::black_box;
+
+fn key_setup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("key setup");
+    group.bench_function("128", |b| {
+        b.iter(|| Twofish::new128(black_box(&[0x42; 16])))
+    });
+    group.bench_function("192", |b| {
+        b.iter(|| Twofish::new192(black_box(&[0x42; 24])))
+    });
+    group.bench_function("256", |b| {
+        b.iter(|| Twofish::new256(black_box(&[0x42; 32])))
+    });
+    group.finish();
+}
+
+#[cfg(feature="bench")]
+fn sbox_setup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("S-box setup");
+    group.bench_function("128", |b| {
+        b.iter(|| Twofish::build_sboxes(black_box(&[0x42; 16])))
+    });
+    group.bench_function("192", |b| {
+        b.iter(|| Twofish::build_sboxes(black_box(&[0x42; 24])))
+    });
+    group.bench_function("256", |b| {
+        b.iter(|| Twofish::build_sboxes(black_box(&[0x42; 32])))
+    });
+    group.finish();
+}
+
+#[cfg(not(feature="bench"))]
+fn sbox_setup(_: &mut Criterion) {}
+
+fn ciphers() -> [(&'static str, Twofish); 3] {
+    [
+        ("128", Twofish::new128(&[0x42; 16])),
+        ("192", Twofish::new192(&[0x42; 24])),
+        ("256", Twofish::new256(&[0x42; 32])),
+    ]
+}
+
+fn single_block(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single block");
+    group.throughput(Throughput::Bytes(16));
+    for (name, twofish) in ciphers().iter() {
+        group.bench_function(*name, |b| {
+            let mut block = [0u8; 16];
+            b.iter(|| {
+                let input = block;
+                twofish.encrypt(black_box(&input), &mut block);
+            })
+        });
+    }
+    group.finish();
+}
+
+fn four_blocks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("four blocks");
+    group.throughput(Throughput::Bytes(64));
+    let twofish = Twofish::new256(&[0x42; 32]);
+    group.bench_function("encrypt_4blocks", |b| {
+        let mut data = [0u8; 64];
+        b.iter(|| {
+            let input = data;
+            twofish.encrypt_4blocks(black_box(&input), &mut data);
+        })
+    });
+    group.bench_function("4x encrypt", |b| {
+        let mut data = [0u8; 64];
+        b.iter(|| {
+            let input = data;
+            for (i, o) in input.chunks_exact(16)
+                .zip(data.chunks_exact_mut(16)) {
+                twofish.encrypt(black_box(i.try_into().unwrap()),
+                                o.try_into().unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn many_blocks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("64 KiB of blocks");
+    group.throughput(Throughput::Bytes(1 << 16));
+    let twofish = Twofish::new256(&[0x42; 32]);
+    let mut data = vec![0u8; 1 << 16];
+    group.bench_function("encrypt_blocks", |b| {
+        b.iter(|| twofish.encrypt_blocks(black_box(&mut data[..])))
+    });
+    group.bench_function("encrypt loop", |b| {
+        b.iter(|| {
+            for block in black_box(&mut data[..]).chunks_exact_mut(16) {
+                let input: [u8; 16] = (&*block).try_into().unwrap();
+                twofish.encrypt(&input, block.try_into().unwrap());
+            }
+        })
+    });
+    group.bench_function("decrypt_blocks", |b| {
+        b.iter(|| twofish.decrypt_blocks(black_box(&mut data[..])))
+    });
+    group.finish();
+}
+
+#[cfg(feature="rayon")]
+fn parallel_ctr(c: &mut Criterion) {
+    use lsx::twofish::Ctr;
+    let mut group = c.benchmark_group("4 MiB CTR");
+    group.throughput(Throughput::Bytes(4 << 20));
+    let twofish = Twofish::new256(&[0x42; 32]);
+    let mut data = vec![0u8; 4 << 20];
+    group.bench_function("serial", |b| {
+        b.iter(|| Ctr::new(&twofish, &[0; 16])
+               .apply_keystream(black_box(&mut data[..])))
+    });
+    group.bench_function("parallel", |b| {
+        let ctr = Ctr::new(&twofish, &[0; 16]);
+        b.iter(|| ctr.apply_keystream_parallel([0; 16],
+                                               black_box(&mut data[..])))
+    });
+    group.finish();
+}
+
+#[cfg(not(feature="rayon"))]
+fn parallel_ctr(_: &mut Criterion) {}
+
+fn bulk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("1 MiB bulk");
+    group.throughput(Throughput::Bytes(1 << 20));
+    for (name, twofish) in ciphers().iter() {
+        let mut data = vec![0u8; 1 << 20];
+        group.bench_function(*name, |b| {
+            b.iter(|| twofish.encrypt_bulk(black_box(&mut data[..])))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, key_setup, sbox_setup, single_block, four_blocks,
+                 many_blocks, parallel_ctr, bulk);
+criterion_main!(benches);
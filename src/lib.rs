@@ -8,9 +8,9 @@
 //! rather than the stack if you want to). Unlike its C99 ancestor, it is
 //! written 100% in safe Rust code. This does not come at any cost in
 //! performance; the Rust version and its C99 ancestor are generally neck and
-//! neck in benchmarks.
-//!
-//! Theoretically, `std` is not required, but this has not been tested.
+//! neck in benchmarks. (The one exception is the optional `"sha-ni"` feature,
+//! which uses the x86 SHA extensions through a small, isolated `unsafe`
+//! module. Without it, the crate is built with `forbid(unsafe_code)`.)
 //!
 //! [1]: https://github.com/SolraBizna/lsx
 //! [2]: https://en.wikipedia.org/wiki/Twofish
@@ -39,6 +39,37 @@
 //!
 //! See the respective module documentation for further information.
 //!
+//! Without `std`
+//! -------------
+//!
+//! The library is `no_std` when the `"std"` feature is off, which means
+//! turning off default features:
+//!
+//! ```toml
+//! lsx = {version = "1.1", default-features = false,
+//!        features = ["sha256", "twofish"]}
+//! ```
+//!
+//! In that configuration, it needs neither `std` nor a heap. Everything is
+//! still there except:
+//!
+//! - Functions that return a `String` or `Vec`, such as `sha256::to_hex`,
+//!   `sha256::format_prefixed`, `sha256::hash_records`,
+//!   `twofish::pad_pkcs7`, `twofish::cbc_decrypt_unpad`, and the `encrypt`
+//!   and `decrypt` methods of the authenticated modes. These need the
+//!   `"alloc"` feature, which needs a global allocator but not `std`. (The
+//!   authenticated modes also have in-place methods that need neither.)
+//! - Functions that do I/O, like `sha256::verify_file`,
+//!   `sha256::hash_reader_and_len`, and `RawSha256::finish_to_writer`.
+//! - The `std::error::Error` impls on the error types. (They still implement
+//!   `Display`.)
+//! - The `"rayon"` and `"sha-ni"` features, which turn `"std"` back on.
+//! - The `"getrandom"` feature, which needs an operating system to get
+//!   random numbers from.
+//!
+//! This is checked by building for `thumbv7em-none-eabi`, which has no `std`
+//! at all.
+//!
 //! License
 //! -------
 //!
@@ -46,10 +77,16 @@
 //! restrictions on use. See `LICENSE.md` for the complete, very short text of
 //! the license.
 
-#![forbid(unsafe_code)]
+// The `"sha-ni"` feature needs `unsafe` for its intrinsics, confined to
+// `sha256::shani`. Every other build forbids it outright.
+#![cfg_attr(not(feature="sha-ni"), forbid(unsafe_code))]
+#![cfg_attr(feature="sha-ni", deny(unsafe_code))]
+#![cfg_attr(not(any(feature="std", test)), no_std)]
 
 #[macro_use]
 extern crate arrayref;
+#[cfg(feature="alloc")]
+extern crate alloc;
 
 #[cfg(feature="sha256")]
 pub mod sha256;
@@ -60,3 +97,37 @@ pub mod twofish;
 pub use sha256::{RawSha256, BufSha256};
 #[cfg(feature="twofish")]
 pub use twofish::Twofish;
+
+/// Features this copy of the library was built with, e.g. for a `--version`
+/// report. Only features that change what the library provides are listed
+/// (`"slow-tests"` isn't).
+///
+/// ```rust
+/// if !lsx::features().contains(&"twofish") {
+///     eprintln!("This build has no Twofish support!");
+/// }
+/// ```
+pub fn features() -> &'static [&'static str] {
+    &[
+        #[cfg(feature="std")] "std",
+        #[cfg(feature="alloc")] "alloc",
+        #[cfg(feature="sha256")] "sha256",
+        #[cfg(feature="twofish")] "twofish",
+        #[cfg(feature="ct-qbox")] "ct-qbox",
+        #[cfg(feature="bench")] "bench",
+        #[cfg(feature="debug-capture")] "debug-capture",
+        #[cfg(feature="simd")] "simd",
+        #[cfg(feature="sha-ni")] "sha-ni",
+        #[cfg(feature="bytes")] "bytes",
+        #[cfg(feature="cipher")] "cipher",
+        #[cfg(feature="getrandom")] "getrandom",
+        #[cfg(feature="rand")] "rand",
+        #[cfg(feature="rayon")] "rayon",
+        #[cfg(feature="serde")] "serde",
+        #[cfg(feature="unicode")] "unicode",
+        #[cfg(feature="zeroize")] "zeroize",
+    ]
+}
+
+#[cfg(test)]
+mod tests;
@@ -4,15 +4,34 @@
 //!
 //! Use [`hash()`][2] if the data you're hashing is already present in
 //! contiguous memory, [`RawSha256`][3] if it is convenient for you to provide
-//! data in 64-byte blocks, or [`BufSha256`][4] otherwise.
+//! data in 64-byte blocks, or [`BufSha256`][4] otherwise. SHA-224, a
+//! truncated SHA-256 with different initial values, is also available, as
+//! `hash224`, `RawSha224`, and `BufSha224`.
 //!
 //! [1]: https://en.wikipedia.org/wiki/SHA-2
 //! [2]: fn.hash.html
 //! [3]: struct.RawSha256.html
 //! [4]: struct.BufSha256.html
 
+use core::sync::atomic::{AtomicU8, Ordering};
+
+mod hmac;
+pub use hmac::{HmacSha256, hmac, hmac_parts};
+mod pbkdf2;
+pub use pbkdf2::pbkdf2;
+mod hkdf;
+pub use hkdf::{hkdf_extract, hkdf_expand, HkdfLengthError};
+#[cfg(feature="serde")]
+mod serde_impl;
+#[cfg(feature="simd")]
+mod simd;
+#[cfg(feature="sha-ni")]
+mod shani;
+
 /// The number of bytes in a SHA-256 hash. (256 bits = 32 bytes)
 pub const HASHBYTES: usize = 32;
+/// The number of bytes in a SHA-224 hash. (224 bits = 28 bytes)
+pub const HASHBYTES_224: usize = 28;
 /// The number of bytes consumed in each "round" of SHA-256. (512 bits = 64
 /// bytes)
 pub const BLOCKBYTES: usize = 64;
@@ -31,9 +50,171 @@ const K: [u32; 64] = [
   0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
 ];
 
+const ZERO_BLOCK: [u8; BLOCKBYTES] = [0; BLOCKBYTES];
+
+/// Which implementation of the SHA-256 compression function to use. See
+/// [`set_backend`](fn.set_backend.html).
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum Backend {
+    /// Use the fastest implementation available on this machine. This is the
+    /// default.
+    Auto,
+    /// Always use the portable scalar implementation.
+    Scalar,
+}
+
+static BACKEND: AtomicU8 = AtomicU8::new(Backend::Auto as u8);
+
+/// Choose which implementation of the SHA-256 compression function to use,
+/// for the whole process. You might want this for reproducible benchmarks,
+/// or to rule out a backend when you suspect a bug.
+///
+/// This is a single process-wide setting, not a per-hasher one. It's safe to
+/// call from any thread at any time; hashers already in progress pick up the
+/// change on their next block. Every backend produces identical results, so
+/// switching in the middle of a hash is harmless.
+///
+/// At the moment, the alternatives to the portable scalar implementation are
+/// the `"simd"` feature's message schedule and the `"sha-ni"` feature's use
+/// of the x86 SHA extensions, both of which `Backend::Auto` uses when
+/// they're enabled (and, for the latter, when the CPU supports them).
+pub fn set_backend(backend: Backend) {
+    BACKEND.store(backend as u8, Ordering::Relaxed);
+}
+
+/// Return the backend most recently chosen by `set_backend`, or
+/// `Backend::Auto` if it was never called.
+pub fn backend() -> Backend {
+    match BACKEND.load(Ordering::Relaxed) {
+        x if x == Backend::Scalar as u8 => Backend::Scalar,
+        _ => Backend::Auto,
+    }
+}
+
+/// Expand a block into the 64-word message schedule, the portable way.
+#[allow(clippy::needless_range_loop)]
+fn schedule_scalar(input: &[u8; BLOCKBYTES]) -> [u32; 64] {
+    let mut w = [0u32; 64];
+    for n in 0..16 {
+        let inblock = array_ref![input, n*4, 4];
+        w[n] = u32::from_be_bytes(*inblock);
+    }
+    expand_schedule(&mut w);
+    w
+}
+
+/// Fill in the rest of a message schedule whose first 16 words are already
+/// there.
+fn expand_schedule(w: &mut [u32; 64]) {
+    for n in 16..64 {
+        let s0 = w[n-15].rotate_right(7) ^ w[n-15].rotate_right(18)
+            ^ (w[n-15]>>3);
+        let s1 = w[n-2].rotate_right(17) ^ w[n-2].rotate_right(19)
+            ^ (w[n-2]>>10);
+        w[n] = w[n-16].wrapping_add(s0)
+            .wrapping_add(w[n-7]).wrapping_add(s1);
+    }
+}
+
+/// Expand a block into the 64-word message schedule, using whichever
+/// implementation the current backend calls for.
+fn schedule(input: &[u8; BLOCKBYTES]) -> [u32; 64] {
+    #[cfg(feature="simd")]
+    if backend() != Backend::Scalar { return simd::schedule(input) }
+    schedule_scalar(input)
+}
+
+/// A finished SHA-256 hash, as a distinct type. It dereferences to the
+/// underlying `[u8; HASHBYTES]`, and formats as lowercase hex with `{}` or
+/// `{:x}` (uppercase with `{:X}`).
+///
+/// Comparing with `==` is *not* constant-time. That's fine for checking the
+/// integrity of data, but not for checking a secret (like a MAC) against one
+/// an attacker gave you; use `verify_eq` for that.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let hash = sha256::hash_typed(b"abc");
+/// assert_eq!(hash.to_string(),
+///            "ba7816bf8f01cfea414140de5dae2223\
+///             b00361a396177a9cb410ff61f20015ad");
+/// assert_eq!(*hash, sha256::hash(b"abc"));
+/// assert_eq!(hash[0], 0xba);
+/// ```
+#[derive(Copy,Clone,PartialEq,Eq)]
+pub struct Sha256Hash([u8; HASHBYTES]);
+
+impl Sha256Hash {
+    /// Return the bytes of the hash.
+    pub fn into_bytes(self) -> [u8; HASHBYTES] {
+        self.0
+    }
+}
+
+impl From<[u8; HASHBYTES]> for Sha256Hash {
+    fn from(bytes: [u8; HASHBYTES]) -> Sha256Hash {
+        Sha256Hash(bytes)
+    }
+}
+
+impl From<Sha256Hash> for [u8; HASHBYTES] {
+    fn from(hash: Sha256Hash) -> [u8; HASHBYTES] {
+        hash.0
+    }
+}
+
+impl core::ops::Deref for Sha256Hash {
+    type Target = [u8; HASHBYTES];
+    fn deref(&self) -> &[u8; HASHBYTES] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Sha256Hash {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[..]
+    }
+}
+
+impl core::fmt::LowerHex for Sha256Hash {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for byte in self.0.iter() {
+            write!(fmt, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::UpperHex for Sha256Hash {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for byte in self.0.iter() {
+            write!(fmt, "{:02X}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Display for Sha256Hash {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        core::fmt::LowerHex::fmt(self, fmt)
+    }
+}
+
+impl core::fmt::Debug for Sha256Hash {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Sha256Hash({:x})", self)
+    }
+}
+
 /// A raw SHA-256 state. This does not include a buffer, so you must provide
 /// data in exact increments of `BLOCKBYTES` (64 bytes).
 ///
+/// With the `"zeroize"` feature, the hashers implement `zeroize::Zeroize`,
+/// which overwrites the state (and `BufSha256`'s buffer) with zeroes. They
+/// are *not* wiped when dropped, since they're `Copy`: call `.zeroize()`
+/// yourself when you're done with one, or keep it in a `zeroize::Zeroizing`.
+///
 /// ```rust
 /// # use lsx::sha256;
 /// # use sha256::RawSha256;
@@ -58,7 +239,6 @@ const K: [u32; 64] = [
 ///             0x42,0x15,0xe0,0xcf,0x9b,0x42,0x48,0x5c,0x99,0xd8,0x0f,0x35,
 ///             0x7d,0x76,0xf0,0x06,0x35,0x9c,0x7a,0x18]);
 /// ```
-
 #[derive(Copy,Clone)]
 pub struct RawSha256 {
     h: [u32; 8],
@@ -90,6 +270,9 @@ pub struct RawSha256 {
 /// let hash2 = hasher.finish(&[]);
 /// assert_eq!(hash1, hash2);
 /// ```
+///
+/// With the `"zeroize"` feature, `.zeroize()` wipes the state and the
+/// buffer. Nothing is wiped on drop; see the note on `RawSha256`.
 #[derive(Copy,Clone)]
 pub struct BufSha256 {
     inner: RawSha256,
@@ -97,7 +280,36 @@ pub struct BufSha256 {
     buffered_bytes: u32,
 }
 
+/// The error returned by
+/// [`RawSha256::try_update`](struct.RawSha256.html#method.try_update) when
+/// it's handed something other than whole blocks.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct BlockSizeError {
+    len: usize,
+}
+
+impl BlockSizeError {
+    /// The length of the offending input, in bytes.
+    pub fn length(&self) -> usize {
+        self.len
+    }
+}
+
+impl core::fmt::Display for BlockSizeError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "{} bytes is not a multiple of the SHA-256 block size",
+               self.len)
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for BlockSizeError {}
+
 impl RawSha256 {
+    /// The number of bytes in a SHA-256 hash. The same as `HASHBYTES`.
+    pub const OUTPUT_LEN: usize = HASHBYTES;
+    /// The number of bytes in a block. The same as `BLOCKBYTES`.
+    pub const BLOCK_LEN: usize = BLOCKBYTES;
     /// Start a new hash.
     pub fn new() -> RawSha256 {
         RawSha256 {
@@ -108,8 +320,62 @@ impl RawSha256 {
             byte_count: 0,
         }
     }
-    /// Perform a single round of SHA-256.
+    /// Resume a hash from a midstate, as returned by `state_words`, and the
+    /// number of bytes that had been processed at that point. Panics if
+    /// `byte_count` is not a multiple of `BLOCKBYTES`.
+    pub fn from_state(h: [u32; 8], byte_count: u64) -> RawSha256 {
+        assert_eq!(byte_count % BLOCKBYTES as u64, 0);
+        RawSha256 { h, byte_count }
+    }
+    /// Return the current (unfinished) hash state, as eight `u32`s. These are
+    /// the standard SHA-256 `h0` through `h7`, in that order. Before any data
+    /// has been processed, they are the initial hash values.
+    ///
+    /// This is *not* the hash! You need `finish` for that.
+    pub fn state_words(&self) -> [u32; 8] {
+        self.h
+    }
+    /// Return the number of bytes processed so far. This is always a
+    /// multiple of `BLOCKBYTES`.
+    pub fn byte_count(&self) -> u64 {
+        self.byte_count
+    }
+    /// Serialize the current state to 40 bytes: the eight state words,
+    /// followed by the byte count, all big-endian. Handy for checkpointing a
+    /// very long hash to disk. `import_state` reverses this.
+    pub fn export_state(&self) -> [u8; 40] {
+        let mut ret = [0u8; 40];
+        for (n, word) in self.h.iter().enumerate() {
+            *array_mut_ref!(ret, n*4, 4) = word.to_be_bytes();
+        }
+        *array_mut_ref!(ret, 32, 8) = self.byte_count.to_be_bytes();
+        ret
+    }
+    /// Resume a hash from the output of `export_state`. Panics if the byte
+    /// count is not a multiple of `BLOCKBYTES`, same as `from_state`.
+    pub fn import_state(bytes: &[u8; 40]) -> RawSha256 {
+        let mut h = [0u32; 8];
+        for (n, word) in h.iter_mut().enumerate() {
+            *word = u32::from_be_bytes(*array_ref!(bytes, n*4, 4));
+        }
+        let byte_count = u64::from_be_bytes(*array_ref!(bytes, 32, 8));
+        RawSha256::from_state(h, byte_count)
+    }
+    /// Run the compression function over some whole blocks, using whichever
+    /// implementation the current backend and CPU call for.
+    fn compress_blocks(&mut self, data: &[u8]) {
+        #[cfg(feature="sha-ni")]
+        if shani::compress(&mut self.h, data) { return }
+        for chunk in data.chunks_exact(BLOCKBYTES) {
+            self.round(array_ref!(chunk, 0, BLOCKBYTES));
+        }
+    }
+    /// Perform a single round of SHA-256, the portable way.
     fn round(&mut self, input: &[u8; BLOCKBYTES]) {
+        self.round_schedule(&schedule(input))
+    }
+    /// Perform a single round of SHA-256, given the message schedule.
+    fn round_schedule(&mut self, w: &[u32; 64]) {
         let mut a = self.h[0];
         let mut b = self.h[1];
         let mut c = self.h[2];
@@ -118,19 +384,6 @@ impl RawSha256 {
         let mut f = self.h[5];
         let mut g = self.h[6];
         let mut h = self.h[7];
-        let mut w = [0u32; 64];
-        for n in 0..16 {
-            let inblock = array_ref![input, n*4, 4];
-            w[n] = u32::from_be_bytes(*inblock);
-        }
-        for n in 16..64 {
-            let s0 = w[n-15].rotate_right(7) ^ w[n-15].rotate_right(18)
-                ^ (w[n-15]>>3);
-            let s1 = w[n-2].rotate_right(17) ^ w[n-2].rotate_right(19)
-                ^ (w[n-2]>>10);
-            w[n] = w[n-16].wrapping_add(s0)
-                .wrapping_add(w[n-7]).wrapping_add(s1);
-        }
         for n in 0..64 {
             let s1 = (e.rotate_right(6) ^ e.rotate_right(11)
                       ^ e.rotate_right(25)).wrapping_add(h)
@@ -151,23 +404,92 @@ impl RawSha256 {
         self.h[7] = self.h[7].wrapping_add(h);
     }
     /// Process some blocks of data. Panics if the input is not an exact
-    /// multiple of `BLOCKBYTES` (64 bytes).
-    pub fn update(&mut self, data: &[u8]) {
+    /// multiple of `BLOCKBYTES` (64 bytes). Returns `self`, so calls can be
+    /// chained.
+    pub fn update(&mut self, data: &[u8]) -> &mut RawSha256 {
         assert_eq!(data.len() % BLOCKBYTES, 0);
-        for chunk in data.chunks_exact(BLOCKBYTES) {
-            self.round(array_ref!(chunk, 0, BLOCKBYTES));
-        }
+        self.compress_blocks(data);
         self.byte_count = self.byte_count.checked_add(data.len() as u64)
             .expect("cannot hash more than 2^61 bytes at a go");
+        self
     }
-    /// Process the remaining data and produce a finished hash. The input does
-    /// *not* need to be a multiple of `BLOCKBYTES`.
-    pub fn finish(mut self, data: &[u8]) -> [u8; HASHBYTES] {
-        let data = if data.len() >= BLOCKBYTES {
-            let extra = data.len() % BLOCKBYTES;
-            self.update(&data[.. data.len()-extra]);
-            &data[data.len()-extra ..]
-        } else { data };
+    /// Like `update`, but takes and returns the state by value, for use in
+    /// expressions.
+    pub fn chain(mut self, data: &[u8]) -> RawSha256 {
+        self.update(data);
+        self
+    }
+    /// Process a single block, given as sixteen 32-bit words instead of 64
+    /// bytes. Each word stands for four bytes of the message, most
+    /// significant first (so word `n` is
+    /// `u32::from_be_bytes(block[n*4 .. n*4+4])`), regardless of the
+    /// target's own byte order. Handy if your data already arrives a word at
+    /// a time; this skips reassembling the words from bytes. Returns `self`,
+    /// so calls can be chained.
+    ///
+    /// This always uses the portable implementation.
+    pub fn round_words(&mut self, words: &[u32; 16]) -> &mut RawSha256 {
+        let mut w = [0u32; 64];
+        w[.. 16].copy_from_slice(words);
+        expand_schedule(&mut w);
+        self.round_schedule(&w);
+        self.byte_count = self.byte_count.checked_add(BLOCKBYTES as u64)
+            .expect("cannot hash more than 2^61 bytes at a go");
+        self
+    }
+    /// Apply the bare SHA-256 compression function to a single block,
+    /// updating the chaining value (`state_words`) and nothing else. **This
+    /// is for experts building their own constructions.** No padding or
+    /// length is ever added, and `byte_count` is left alone, so mixing this
+    /// with `update` and `finish` won't give you a SHA-256 hash of anything.
+    /// Returns `self`, so calls can be chained.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{RawSha256, BLOCKBYTES};
+    /// let mut hasher = RawSha256::new();
+    /// hasher.compress(&[0x42; BLOCKBYTES]);
+    /// assert_eq!(hasher.byte_count(), 0);
+    /// assert_ne!(hasher.state_words(), RawSha256::new().state_words());
+    /// ```
+    pub fn compress(&mut self, block: &[u8; BLOCKBYTES]) -> &mut RawSha256 {
+        self.compress_blocks(block);
+        self
+    }
+    /// Process some blocks of data, like `update`, but return an error
+    /// instead of panicking if the input is not an exact multiple of
+    /// `BLOCKBYTES`. Nothing is processed in that case.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{RawSha256, BLOCKBYTES};
+    /// let mut hasher = RawSha256::new();
+    /// assert!(hasher.try_update(&[0; BLOCKBYTES * 2]).is_ok());
+    /// assert_eq!(hasher.try_update(&[0; 100]).unwrap_err().length(), 100);
+    /// ```
+    // (`is_multiple_of` is only stable since Rust 1.87)
+    #[allow(clippy::manual_is_multiple_of)]
+    pub fn try_update(&mut self, data: &[u8])
+                      -> Result<(), BlockSizeError> {
+        if data.len() % BLOCKBYTES != 0 {
+            return Err(BlockSizeError { len: data.len() })
+        }
+        self.update(data);
+        Ok(())
+    }
+    /// Process the given number of all-zero blocks.
+    fn update_zero_blocks(&mut self, blocks: u64) {
+        self.byte_count = blocks.checked_mul(BLOCKBYTES as u64)
+            .and_then(|x| self.byte_count.checked_add(x))
+            .expect("cannot hash more than 2^61 bytes at a go");
+        for _ in 0 .. blocks {
+            self.compress_blocks(&ZERO_BLOCK);
+        }
+    }
+    /// Build the final block(s) for a hash whose last (partial) block is
+    /// `data`: the data itself, the `0x80` marker, and the bit count. Returns
+    /// the blocks and how many bytes of them are used (one or two blocks'
+    /// worth).
+    fn padding(&self, data: &[u8]) -> ([u8; BLOCKBYTES*2], usize) {
+        debug_assert!(data.len() < BLOCKBYTES);
         let byte_count = self.byte_count.checked_add(data.len() as u64)
             .expect("cannot hash more than 2^61 bytes at a go");
         if byte_count >= 0x2000000000000000 {
@@ -176,31 +498,102 @@ impl RawSha256 {
         let mut block = [0u8; BLOCKBYTES*2];
         block[..data.len()].copy_from_slice(data);
         block[data.len()] = 0x80;
-        if data.len() > BLOCKBYTES - 9 {
-            block[BLOCKBYTES*2-8 .. BLOCKBYTES*2]
-                .copy_from_slice(&(byte_count << 3).to_be_bytes()[..]);
-            self.round(array_ref!(block, 0, BLOCKBYTES));
-            self.round(array_ref!(block, BLOCKBYTES, BLOCKBYTES));
-        }
-        else {
-            block[BLOCKBYTES-8 .. BLOCKBYTES]
-                .copy_from_slice(&(byte_count << 3).to_be_bytes()[..]);
-            self.round(array_ref!(block, 0, BLOCKBYTES));
-        }
+        let len = if data.len() > BLOCKBYTES - 9 { BLOCKBYTES*2 }
+        else { BLOCKBYTES };
+        block[len-8 .. len]
+            .copy_from_slice(&(byte_count << 3).to_be_bytes()[..]);
+        (block, len)
+    }
+    /// Produce the hash from the current state. Only meaningful once the
+    /// padding has been processed.
+    fn output(&self) -> [u8; HASHBYTES] {
         let mut ret = [0u8; HASHBYTES];
-        ret[ 0.. 4].copy_from_slice(&self.h[0].to_be_bytes()[..]);
-        ret[ 4.. 8].copy_from_slice(&self.h[1].to_be_bytes()[..]);
-        ret[ 8..12].copy_from_slice(&self.h[2].to_be_bytes()[..]);
-        ret[12..16].copy_from_slice(&self.h[3].to_be_bytes()[..]);
-        ret[16..20].copy_from_slice(&self.h[4].to_be_bytes()[..]);
-        ret[20..24].copy_from_slice(&self.h[5].to_be_bytes()[..]);
-        ret[24..28].copy_from_slice(&self.h[6].to_be_bytes()[..]);
-        ret[28..32].copy_from_slice(&self.h[7].to_be_bytes()[..]);
+        self.output_into(&mut ret);
         ret
     }
+    /// Like `output`, but writes the hash into `out`.
+    fn output_into(&self, out: &mut [u8; HASHBYTES]) {
+        out[ 0.. 4].copy_from_slice(&self.h[0].to_be_bytes()[..]);
+        out[ 4.. 8].copy_from_slice(&self.h[1].to_be_bytes()[..]);
+        out[ 8..12].copy_from_slice(&self.h[2].to_be_bytes()[..]);
+        out[12..16].copy_from_slice(&self.h[3].to_be_bytes()[..]);
+        out[16..20].copy_from_slice(&self.h[4].to_be_bytes()[..]);
+        out[20..24].copy_from_slice(&self.h[5].to_be_bytes()[..]);
+        out[24..28].copy_from_slice(&self.h[6].to_be_bytes()[..]);
+        out[28..32].copy_from_slice(&self.h[7].to_be_bytes()[..]);
+    }
+    /// Process the remaining data and produce a finished hash. The input does
+    /// *not* need to be a multiple of `BLOCKBYTES`.
+    pub fn finish(self, data: &[u8]) -> [u8; HASHBYTES] {
+        let mut ret = [0u8; HASHBYTES];
+        self.finish_into(data, &mut ret);
+        ret
+    }
+    /// Like `finish`, but writes the finished hash into `out` instead of
+    /// returning it. Handy when the hash lives at a fixed spot in some bigger
+    /// buffer.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, RawSha256, HASHBYTES};
+    /// let mut message = [0u8; 4 + HASHBYTES];
+    /// message[..4].copy_from_slice(b"HASH");
+    /// RawSha256::new().finish_into(b"abc",
+    ///     arrayref::array_mut_ref!(message, 4, HASHBYTES));
+    /// assert_eq!(message[4..], sha256::hash(b"abc"));
+    /// ```
+    pub fn finish_into(mut self, data: &[u8], out: &mut [u8; HASHBYTES]) {
+        self.finish_in_place(data, out)
+    }
+    /// Like `finish`, but the remaining data is the first `len` bytes of a
+    /// whole block-sized buffer, as with a DMA buffer that's always
+    /// `BLOCKBYTES` long but may be only partly filled. The rest of the
+    /// buffer is ignored. Panics if `len` is more than `BLOCKBYTES`.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, RawSha256, BLOCKBYTES};
+    /// let mut buffer = [0xFF; BLOCKBYTES];
+    /// buffer[..3].copy_from_slice(b"abc");
+    /// assert_eq!(RawSha256::new().finish_partial(&buffer, 3),
+    ///            sha256::hash(b"abc"));
+    /// ```
+    pub fn finish_partial(self, block: &[u8; BLOCKBYTES], len: usize)
+                          -> [u8; HASHBYTES] {
+        assert!(len <= BLOCKBYTES, "partial block is longer than a block");
+        self.finish(&block[.. len])
+    }
+    /// Produce the hash you'd get by calling `finish` right now, without
+    /// consuming this state. You can keep hashing afterwards.
+    pub fn peek_finish(&self, data: &[u8]) -> [u8; HASHBYTES] {
+        let state = *self;
+        state.finish(data)
+    }
+    /// Like `finish_into`, but leaves the (now meaningless) state behind
+    /// instead of consuming it.
+    fn finish_in_place(&mut self, data: &[u8], out: &mut [u8; HASHBYTES]) {
+        let data = if data.len() >= BLOCKBYTES {
+            let extra = data.len() % BLOCKBYTES;
+            self.update(&data[.. data.len()-extra]);
+            &data[data.len()-extra ..]
+        } else { data };
+        let (block, len) = self.padding(data);
+        self.compress_blocks(&block[.. len]);
+        self.output_into(out)
+    }
+    /// Process the remaining data, and write the finished hash to the given
+    /// writer. The input does *not* need to be a multiple of `BLOCKBYTES`.
+    /// Requires the `"std"` feature (enabled by default).
+    #[cfg(feature="std")]
+    pub fn finish_to_writer<W: std::io::Write>(self, data: &[u8], w: &mut W)
+        -> std::io::Result<()> {
+        w.write_all(&self.finish(data)[..])
+    }
 }
 
 impl BufSha256 {
+    /// The number of bytes in a SHA-256 hash. The same as `HASHBYTES`.
+    pub const OUTPUT_LEN: usize = HASHBYTES;
+    /// The number of bytes in a block. The same as `BLOCKBYTES`.
+    pub const BLOCK_LEN: usize = BLOCKBYTES;
     /// Initialize a SHA-256 state.
     pub fn new() -> BufSha256 {
         BufSha256 {
@@ -209,8 +602,21 @@ impl BufSha256 {
             buffered_bytes: 0,
         }
     }
+    /// Return the number of bytes processed so far, including any that are
+    /// still waiting in the buffer.
+    pub fn byte_count(&self) -> u64 {
+        self.inner.byte_count + self.buffered_bytes as u64
+    }
     /// Process some data. You may provide any amount of data you wish.
-    pub fn update(&mut self, mut data: &[u8]) {
+    /// Returns `self`, so calls can be chained.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, BufSha256};
+    /// let mut hasher = BufSha256::new();
+    /// hasher.update(b"Hello").update(b", ");
+    /// assert_eq!(hasher.finish(b"World!"), sha256::hash(b"Hello, World!"));
+    /// ```
+    pub fn update(&mut self, mut data: &[u8]) -> &mut BufSha256 {
         if self.buffered_bytes > 0 {
             let remaining_bytes = BLOCKBYTES - (self.buffered_bytes as usize);
             if remaining_bytes <= data.len() {
@@ -223,9 +629,9 @@ impl BufSha256 {
             else {
                 self.buf[self.buffered_bytes as usize ..
                          self.buffered_bytes as usize + data.len()]
-                    .copy_from_slice(&data[..]);
-                self.buffered_bytes = self.buffered_bytes + data.len() as u32;
-                return;
+                    .copy_from_slice(data);
+                self.buffered_bytes += data.len() as u32;
+                return self
             }
         }
         debug_assert_eq!(self.buffered_bytes, 0);
@@ -237,11 +643,335 @@ impl BufSha256 {
         debug_assert!(data.len() < BLOCKBYTES);
         self.buf[..data.len()].copy_from_slice(data);
         self.buffered_bytes = data.len() as u32;
+        self
+    }
+    /// Like `update`, but takes and returns the state by value, for use in
+    /// expressions.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, BufSha256};
+    /// let hash = BufSha256::new().chain(b"Hello").chain(b", ")
+    ///     .finish(b"World!");
+    /// assert_eq!(hash, sha256::hash(b"Hello, World!"));
+    /// ```
+    pub fn chain(mut self, data: &[u8]) -> BufSha256 {
+        self.update(data);
+        self
+    }
+    /// Process several pieces of data, in order, exactly as if they were one
+    /// contiguous buffer passed to `update`. Handy for scatter-gather I/O.
+    /// Whole blocks within each piece go straight to the compression
+    /// function; only the bytes that straddle a boundary between pieces get
+    /// copied into the buffer. Returns `self`, so calls can be chained.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, BufSha256};
+    /// let mut hasher = BufSha256::new();
+    /// hasher.update_vectored(&[b"Hello", b", ", b"World!"]);
+    /// assert_eq!(hasher.finish(&[]), sha256::hash(b"Hello, World!"));
+    /// ```
+    pub fn update_vectored(&mut self, slices: &[&[u8]]) -> &mut BufSha256 {
+        for slice in slices {
+            self.update(slice);
+        }
+        self
+    }
+    /// Process some data, like `update`, but call `on_block` with each
+    /// complete block as it is processed. Taken together, the blocks seen by
+    /// `on_block` are exactly the data provided so far, minus whatever is
+    /// still waiting in the buffer.
+    pub fn update_blockwise<F: FnMut(&[u8; BLOCKBYTES])>(&mut self,
+                                                        mut data: &[u8],
+                                                        mut on_block: F) {
+        if self.buffered_bytes > 0 {
+            let remaining_bytes = BLOCKBYTES - (self.buffered_bytes as usize);
+            if remaining_bytes > data.len() {
+                self.update(data);
+                return;
+            }
+            self.buf[self.buffered_bytes as usize ..]
+                .copy_from_slice(&data[.. remaining_bytes]);
+            self.inner.update(&self.buf[..]);
+            on_block(&self.buf);
+            self.buffered_bytes = 0;
+            data = &data[remaining_bytes ..];
+        }
+        let mut chunks = data.chunks_exact(BLOCKBYTES);
+        for chunk in &mut chunks {
+            self.inner.update(chunk);
+            on_block(array_ref!(chunk, 0, BLOCKBYTES));
+        }
+        self.update(chunks.remainder());
+    }
+    /// Process `count` zero bytes, exactly as if you had passed that many
+    /// zeroes to `update`, but without needing a buffer full of zeroes to do
+    /// it. Handy for hashing large, sparse data.
+    pub fn update_zeros(&mut self, mut count: u64) {
+        if self.buffered_bytes > 0 {
+            let remaining_bytes = BLOCKBYTES - (self.buffered_bytes as usize);
+            let amount = count.min(remaining_bytes as u64);
+            self.update(&ZERO_BLOCK[.. amount as usize]);
+            count -= amount;
+        }
+        if count >= BLOCKBYTES as u64 {
+            debug_assert_eq!(self.buffered_bytes, 0);
+            self.inner.update_zero_blocks(count / BLOCKBYTES as u64);
+            count %= BLOCKBYTES as u64;
+        }
+        self.update(&ZERO_BLOCK[.. count as usize]);
+    }
+    /// Process a `u32`, in little-endian byte order.
+    pub fn write_u32_le(&mut self, x: u32) {
+        self.update(&x.to_le_bytes()[..]);
+    }
+    /// Process a `u32`, in big-endian byte order.
+    pub fn write_u32_be(&mut self, x: u32) {
+        self.update(&x.to_be_bytes()[..]);
+    }
+    /// Process a `u64`, in little-endian byte order.
+    pub fn write_u64_le(&mut self, x: u64) {
+        self.update(&x.to_le_bytes()[..]);
+    }
+    /// Process a `u64`, in big-endian byte order.
+    pub fn write_u64_be(&mut self, x: u64) {
+        self.update(&x.to_be_bytes()[..]);
+    }
+    /// Process any remaining data and produce a finished hash.
+    pub fn finish(self, data: &[u8]) -> [u8; HASHBYTES] {
+        let mut ret = [0u8; HASHBYTES];
+        self.finish_into(data, &mut ret);
+        ret
+    }
+    /// Like `finish`, but writes the finished hash into `out` instead of
+    /// returning it.
+    pub fn finish_into(mut self, data: &[u8], out: &mut [u8; HASHBYTES]) {
+        if !data.is_empty() { self.update(data); }
+        self.inner.finish_in_place(&self.buf[.. self.buffered_bytes as usize],
+                                   out)
+    }
+    /// Produce the hash you'd get by calling `finish` right now, without
+    /// consuming this state. You can keep hashing afterwards.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, BufSha256};
+    /// let mut hasher = BufSha256::new();
+    /// hasher.update(b"Hello");
+    /// assert_eq!(hasher.peek_finish(b"!"), sha256::hash(b"Hello!"));
+    /// hasher.update(b", World");
+    /// assert_eq!(hasher.finish(b"!"), sha256::hash(b"Hello, World!"));
+    /// ```
+    pub fn peek_finish(&self, data: &[u8]) -> [u8; HASHBYTES] {
+        let state = *self;
+        state.finish(data)
+    }
+    /// Do everything `finish` would do except process the final block(s):
+    /// return them (the buffered data, followed by SHA-256's padding) along
+    /// with how many bytes of them are used, 64 or 128. Pass exactly those
+    /// bytes to `finalize_padded` to get the hash. In between, you're free to
+    /// do whatever you like, e.g. send them somewhere separately.
+    ///
+    /// Afterwards, the buffer is empty. Don't `update` this state again;
+    /// `finalize_padded` is the only sensible thing left to do with it.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, BufSha256};
+    /// let mut hasher = BufSha256::new();
+    /// hasher.update(b"Hello World!");
+    /// let (padding, len) = hasher.pad();
+    /// assert_eq!(len, 64);
+    /// assert_eq!(hasher.finalize_padded(&padding[.. len]),
+    ///            sha256::hash(b"Hello World!"));
+    /// ```
+    pub fn pad(&mut self) -> ([u8; BLOCKBYTES*2], usize) {
+        let buffered = &self.buf[.. self.buffered_bytes as usize];
+        let ret = self.inner.padding(buffered);
+        self.buffered_bytes = 0;
+        ret
+    }
+    /// Process the final block(s) returned by `pad`, and produce the finished
+    /// hash. Panics if `padding` isn't a whole number of blocks.
+    pub fn finalize_padded(mut self, padding: &[u8]) -> [u8; HASHBYTES] {
+        debug_assert_eq!(self.buffered_bytes, 0);
+        self.inner.update(padding);
+        self.inner.output()
+    }
+    /// Process any remaining data and produce a finished hash, as a
+    /// `Sha256Hash`.
+    pub fn finish_typed(self, data: &[u8]) -> Sha256Hash {
+        Sha256Hash(self.finish(data))
+    }
+    /// Produce a finished hash, write it into `out`, and reset this state so
+    /// that it's ready to hash a new message (as if freshly created with
+    /// `new`, including a zeroed buffer).
+    pub fn finalize_into_reset(&mut self, out: &mut [u8; HASHBYTES]) {
+        *out = core::mem::take(self).finish(&[]);
+    }
+    /// Switch to the unbuffered `RawSha256` underneath, e.g. once a stream
+    /// has become block-aligned. This only works if nothing is waiting in the
+    /// buffer (i.e. `byte_count` is a multiple of `BLOCKBYTES`); otherwise,
+    /// the state is handed back unchanged as the error.
+    ///
+    /// ```rust
+    /// # use lsx::sha256::{self, BufSha256, BLOCKBYTES};
+    /// let mut hasher = BufSha256::new();
+    /// hasher.update(b"header");
+    /// let mut hasher = hasher.into_raw().unwrap_err();
+    /// hasher.update(&[0; BLOCKBYTES - 6]);
+    /// let raw = hasher.into_raw().unwrap();
+    /// assert_eq!(raw.byte_count(), BLOCKBYTES as u64);
+    /// ```
+    pub fn into_raw(mut self) -> Result<RawSha256, BufSha256> {
+        if self.buffered_bytes == 0 {
+            Ok(core::mem::take(&mut self.inner))
+        }
+        else { Err(self) }
+    }
+}
+
+/// The initial hash values for SHA-224. Everything else about SHA-224 is the
+/// same as SHA-256, except that the output is truncated.
+const H224: [u32; 8] = [
+    0xc1059ed8, 0x367cd507, 0x3070dd17, 0xf70e5939,
+    0xffc00b31, 0x68581511, 0x64f98fa7, 0xbefa4fa4,
+];
+
+/// Cut a finished SHA-256-style state down to a SHA-224 hash.
+fn truncate_224(hash: [u8; HASHBYTES]) -> [u8; HASHBYTES_224] {
+    *array_ref!(hash, 0, HASHBYTES_224)
+}
+
+/// A raw SHA-224 state. Like `RawSha256`, you must provide data in exact
+/// increments of `BLOCKBYTES` (64 bytes). SHA-224 is SHA-256 with different
+/// initial values and a shorter output, so this is just a thin wrapper.
+#[derive(Copy,Clone)]
+pub struct RawSha224 {
+    inner: RawSha256,
+}
+
+impl RawSha224 {
+    /// The number of bytes in a SHA-224 hash. The same as `HASHBYTES_224`.
+    pub const OUTPUT_LEN: usize = HASHBYTES_224;
+    /// The number of bytes in a block. The same as `BLOCKBYTES`.
+    pub const BLOCK_LEN: usize = BLOCKBYTES;
+    /// Start a new hash.
+    pub fn new() -> RawSha224 {
+        RawSha224 { inner: RawSha256::from_state(H224, 0) }
+    }
+    /// Process some blocks of data. Panics if the input is not an exact
+    /// multiple of `BLOCKBYTES` (64 bytes).
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+    /// Process the remaining data and produce a finished hash. The input does
+    /// *not* need to be a multiple of `BLOCKBYTES`.
+    pub fn finish(self, data: &[u8]) -> [u8; HASHBYTES_224] {
+        truncate_224(self.inner.finish(data))
+    }
+}
+
+/// A SHA-224 state, including a buffer to allow non-block-sized inputs. See
+/// `RawSha224`.
+///
+/// ```rust
+/// # use lsx::sha256::BufSha224;
+/// let mut hasher = BufSha224::new();
+/// hasher.update(b"a");
+/// hasher.update(b"bc");
+/// assert_eq!(hasher.finish(&[]),
+///            [0x23,0x09,0x7d,0x22,0x34,0x05,0xd8,0x22,0x86,0x42,0xa4,0x77,
+///             0xbd,0xa2,0x55,0xb3,0x2a,0xad,0xbc,0xe4,0xbd,0xa0,0xb3,0xf7,
+///             0xe3,0x6c,0x9d,0xa7]);
+/// ```
+#[derive(Copy,Clone)]
+pub struct BufSha224 {
+    inner: BufSha256,
+}
+
+impl BufSha224 {
+    /// The number of bytes in a SHA-224 hash. The same as `HASHBYTES_224`.
+    pub const OUTPUT_LEN: usize = HASHBYTES_224;
+    /// The number of bytes in a block. The same as `BLOCKBYTES`.
+    pub const BLOCK_LEN: usize = BLOCKBYTES;
+    /// Initialize a SHA-224 state.
+    pub fn new() -> BufSha224 {
+        BufSha224 {
+            inner: BufSha256 {
+                inner: RawSha256::from_state(H224, 0),
+                buf: [0u8; BLOCKBYTES],
+                buffered_bytes: 0,
+            },
+        }
+    }
+    /// Process some data. You may provide any amount of data you wish.
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+    /// Process any remaining data and produce a finished hash.
+    pub fn finish(self, data: &[u8]) -> [u8; HASHBYTES_224] {
+        truncate_224(self.inner.finish(data))
+    }
+}
+
+/// A `BufSha256` that also keeps a copy of the first `N` bytes it was given,
+/// so that you can see exactly what was hashed when a hash doesn't come out
+/// the way you expected. Requires the `"debug-capture"` feature. This is a
+/// development aid; it doesn't exist at all unless you ask for it.
+///
+/// The copy lives inside the struct itself, so it costs `N` bytes of memory
+/// (and no heap). Input beyond the first `N` bytes is still hashed, but not
+/// captured.
+///
+/// ```rust
+/// # use lsx::sha256::{self, CapturingSha256};
+/// let mut hasher = CapturingSha256::<8>::new();
+/// hasher.update(b"Hello ");
+/// hasher.update(b"World!");
+/// assert_eq!(hasher.captured(), b"Hello Wo");
+/// assert_eq!(hasher.finish(&[]), sha256::hash(b"Hello World!"));
+/// ```
+#[cfg(feature="debug-capture")]
+#[derive(Copy,Clone)]
+pub struct CapturingSha256<const N: usize> {
+    inner: BufSha256,
+    captured: [u8; N],
+    captured_bytes: usize,
+}
+
+#[cfg(feature="debug-capture")]
+#[allow(clippy::new_without_default)]
+impl<const N: usize> CapturingSha256<N> {
+    /// Initialize a SHA-256 state.
+    pub fn new() -> CapturingSha256<N> {
+        CapturingSha256 {
+            inner: BufSha256::new(),
+            captured: [0u8; N],
+            captured_bytes: 0,
+        }
+    }
+    /// Process some data. You may provide any amount of data you wish.
+    pub fn update(&mut self, data: &[u8]) {
+        let amount = data.len().min(N - self.captured_bytes);
+        self.captured[self.captured_bytes .. self.captured_bytes + amount]
+            .copy_from_slice(&data[.. amount]);
+        self.captured_bytes += amount;
+        self.inner.update(data);
+    }
+    /// Return the data captured so far (at most `N` bytes).
+    pub fn captured(&self) -> &[u8] {
+        &self.captured[.. self.captured_bytes]
     }
     /// Process any remaining data and produce a finished hash.
     pub fn finish(mut self, data: &[u8]) -> [u8; HASHBYTES] {
-        if data.len() != 0 { self.update(data) }
-        self.inner.finish(&self.buf[.. self.buffered_bytes as usize])
+        if !data.is_empty() { self.update(data) }
+        self.inner.finish(&[])
+    }
+}
+
+#[cfg(feature="debug-capture")]
+impl<const N: usize> core::fmt::Debug for CapturingSha256<N> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "CapturingSha256 {{ captured: {:?}, ... }}",
+               self.captured())
     }
 }
 
@@ -260,17 +990,893 @@ pub fn hash(data: &[u8]) -> [u8; HASHBYTES] {
     RawSha256::new().finish(data)
 }
 
+/// Calculate the SHA-256 hash of the UTF-8 encoding of a string, after
+/// converting it to Unicode Normalization Form C ([NFC][1], canonical
+/// composition). Strings that are canonically equivalent, like `"é"` spelled
+/// as one code point (U+00E9) or as two (U+0065 U+0301), get the same hash.
+/// Only canonical equivalence is considered; compatibility equivalents (e.g.
+/// `"ﬁ"` and `"fi"`) still hash differently. Requires the `"unicode"`
+/// feature.
+///
+/// The normalized string is hashed as it's produced, without being stored
+/// anywhere.
+///
+/// [1]: https://unicode.org/reports/tr15/
+#[cfg(feature="unicode")]
+pub fn hash_str_nfc(s: &str) -> [u8; HASHBYTES] {
+    use unicode_normalization::UnicodeNormalization;
+    let mut hasher = BufSha256::new();
+    let mut buf = [0u8; 4];
+    for c in s.nfc() {
+        hasher.update(c.encode_utf8(&mut buf).as_bytes());
+    }
+    hasher.finish(&[])
+}
+
+/// Calculate the SHA-256 hash of everything remaining in a
+/// [`bytes::Buf`](https://docs.rs/bytes/1/bytes/trait.Buf.html), chunk by
+/// chunk, without copying it into one contiguous place first. The buffer is
+/// consumed (advanced to the end). Requires the `"bytes"` feature.
+#[cfg(feature="bytes")]
+pub fn hash_buf<B: bytes::Buf>(buf: &mut B) -> [u8; HASHBYTES] {
+    let mut hasher = BufSha256::new();
+    while buf.has_remaining() {
+        let chunk = buf.chunk();
+        let len = chunk.len();
+        hasher.update(chunk);
+        buf.advance(len);
+    }
+    hasher.finish(&[])
+}
+
+/// Calculate the SHA-256 hash of a given byte string, as a `Sha256Hash`.
+pub fn hash_typed(data: &[u8]) -> Sha256Hash {
+    Sha256Hash(hash(data))
+}
+
+/// Calculate the SHA-224 hash of a given byte string.
+pub fn hash224(data: &[u8]) -> [u8; HASHBYTES_224] {
+    RawSha224::new().finish(data)
+}
+
+/// Calculate the SHA-256 hash of a given byte string, and write it into the
+/// first `HASHBYTES` bytes of `out`. If `out` is too small, returns
+/// `Err(HASHBYTES)` (the length it needs to be) and leaves it untouched.
+/// Useful for writing directly into a caller-provided buffer, e.g. across an
+/// FFI boundary.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let mut out = [0u8; 40];
+/// assert_eq!(sha256::hash_into(b"abc", &mut out[..]), Ok(()));
+/// assert_eq!(out[..32], sha256::hash(b"abc"));
+/// assert_eq!(sha256::hash_into(b"abc", &mut out[..31]), Err(32));
+/// ```
+pub fn hash_into(data: &[u8], out: &mut [u8]) -> Result<(), usize> {
+    if out.len() < HASHBYTES { return Err(HASHBYTES) }
+    out[.. HASHBYTES].copy_from_slice(&hash(data)[..]);
+    Ok(())
+}
+
+/// Calculate the SHA-256 hash of a given byte string, and also return its
+/// first four bytes as a big-endian `u32`. This is handy for indexing (e.g.
+/// bucketing by a short prefix while keeping the full hash for integrity).
+/// The prefix is derived from the full hash, so it carries no information
+/// that the hash doesn't.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let (hash, prefix) = sha256::hash_with_prefix(b"abc");
+/// assert_eq!(hash, sha256::hash(b"abc"));
+/// assert_eq!(prefix, 0xba7816bf);
+/// ```
+pub fn hash_with_prefix(data: &[u8]) -> ([u8; HASHBYTES], u32) {
+    let hash = hash(data);
+    let prefix = u32::from_be_bytes(*array_ref!(hash, 0, 4));
+    (hash, prefix)
+}
+
+/// Calculate the SHA-256 hash of a matrix (or other two-dimensional array)
+/// stored in row-major order, committing to its shape. The number of rows and
+/// the number of columns are hashed first, each as a big-endian `u64`,
+/// followed by `data`. This way, a 2x3 matrix never collides with a 3x2
+/// matrix that happens to contain the same bytes.
+///
+/// `data` may use any element size, but its length must be an exact multiple
+/// of `rows * cols`. Panics if it isn't.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let data = [1, 2, 3, 4, 5, 6];
+/// assert_ne!(sha256::hash_matrix(2, 3, &data),
+///            sha256::hash_matrix(3, 2, &data));
+/// ```
+pub fn hash_matrix(rows: usize, cols: usize, data: &[u8]) -> [u8; HASHBYTES] {
+    let elements = rows.checked_mul(cols).expect("matrix is too large");
+    if elements == 0 { assert!(data.is_empty()) }
+    else { assert_eq!(data.len() % elements, 0) }
+    let mut hasher = BufSha256::new();
+    hasher.write_u64_be(rows as u64);
+    hasher.write_u64_be(cols as u64);
+    hasher.finish(data)
+}
+
+/// Calculate the SHA-256 hash of a list of byte strings, in a way that is
+/// unambiguous no matter what the strings contain. The number of items is
+/// hashed first, as a big-endian `u64`, followed by each item's length (also a
+/// big-endian `u64`) and contents. This way, `["ab", "c"]` and `["a", "bc"]`
+/// do not collide, as they would if you just hashed the concatenation.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// assert_ne!(sha256::hash_list(&[b"ab", b"c"]),
+///            sha256::hash_list(&[b"a", b"bc"]));
+/// ```
+pub fn hash_list(items: &[&[u8]]) -> [u8; HASHBYTES] {
+    let mut hasher = BufSha256::new();
+    hasher.write_u64_be(items.len() as u64);
+    for item in items {
+        hasher.write_u64_be(item.len() as u64);
+        hasher.update(item);
+    }
+    hasher.finish(&[])
+}
+
+/// Hash `data`, then hash the resulting hash, and so on, for a total of
+/// `rounds` hashes. That is, `hash_iterated(data, 1)` is `hash(data)`,
+/// `hash_iterated(data, 2)` is `hash(&hash(data))`, etc. At least one round is
+/// always performed, so `rounds == 0` is the same as `rounds == 1`.
+///
+/// This is a *weak* way to stretch a password into a key, and is only
+/// provided so that you can interoperate with systems that do exactly this.
+/// If you have a choice, use PBKDF2 or something better instead.
+pub fn hash_iterated(data: &[u8], rounds: u32) -> [u8; HASHBYTES] {
+    let mut ret = hash(data);
+    for _ in 1 .. rounds {
+        ret = hash(&ret[..]);
+    }
+    ret
+}
+
+/// Calculate a "tagged hash" of `data`, as in [BIP 340][1]:
+/// `hash(hash(tag) || hash(tag) || data)`. Hashes made with different tags
+/// can't be confused with each other, or with plain hashes, so this is a
+/// cheap way to keep hashes used for different purposes apart ("domain
+/// separation").
+///
+/// [1]: https://github.com/bitcoin/bips/blob/master/bip-0340.mediawiki
+pub fn tagged_hash(tag: &[u8], data: &[u8]) -> [u8; HASHBYTES] {
+    let tag_hash = hash(tag);
+    let mut hasher = BufSha256::new();
+    hasher.update(&tag_hash[..]);
+    hasher.update(&tag_hash[..]);
+    hasher.finish(data)
+}
+
+/// Calculate a commitment to a secret key (e.g. a Twofish key): a
+/// `tagged_hash` of the raw key bytes, with the tag `"lsx/commit-key"`. You
+/// can publish the commitment now, and later prove which key you used by
+/// revealing it.
+///
+/// Two commitments are equal if and only if the keys are, and that's all the
+/// commitment reveals about the key, *provided the key is random*. If the
+/// "key" is something guessable, like a password, an attacker can simply
+/// hash guesses until one matches.
+pub fn commit_key(key: &[u8]) -> [u8; HASHBYTES] {
+    tagged_hash(b"lsx/commit-key", key)
+}
+
+/// Combine two child nodes of a Merkle tree into their parent:
+/// `hash(0x01 || left || right)`.
+fn merkle_node(left: &[u8; HASHBYTES], right: &[u8; HASHBYTES])
+               -> [u8; HASHBYTES] {
+    let mut hasher = BufSha256::new();
+    hasher.update(&[1]);
+    hasher.update(&left[..]);
+    hasher.finish(&right[..])
+}
+
+/// Calculate the root of a Merkle tree whose leaves are the given hashes.
+///
+/// The tree has the same shape as in [RFC 6962][1]: with `n > 1` leaves, the
+/// left subtree gets the largest power of two that is less than `n`, and the
+/// right subtree gets the rest. Each parent node is `hash(0x01 || left ||
+/// right)`. The leaves are used as-is, so a tree with one leaf has that leaf
+/// as its root. (Unlike RFC 6962, you pass in the leaf hashes rather than the
+/// leaf data; hash them however you like.) A tree with no leaves has
+/// `hash(b"")` as its root.
+///
+/// No heap allocation is needed.
+///
+/// [1]: https://www.rfc-editor.org/rfc/rfc6962#section-2.1
+pub fn merkle_root(leaves: &[[u8; HASHBYTES]]) -> [u8; HASHBYTES] {
+    match leaves.len() {
+        0 => hash(&[]),
+        1 => leaves[0],
+        n => {
+            let split = n.next_power_of_two() / 2;
+            merkle_node(&merkle_root(&leaves[.. split]),
+                        &merkle_root(&leaves[split ..]))
+        },
+    }
+}
+
+/// Builds a Merkle tree one leaf at a time, without keeping all the leaves
+/// around. Only the roots of the complete subtrees built so far (at most one
+/// per bit of the leaf count, like a Merkle mountain range) are kept, so this
+/// needs no heap, and its size is fixed at about 2KiB no matter how many
+/// leaves you push.
+///
+/// `push_leaf` hashes its data with plain SHA-256 to make the leaf, and
+/// `push_leaf_hash` takes a leaf hash directly. Either way, `finish` returns
+/// exactly what [`merkle_root`](fn.merkle_root.html) would for the same leaf
+/// hashes: the same tree shape, and parents combined as
+/// `hash(0x01 || left || right)`.
+///
+/// ```rust
+/// # use lsx::sha256::{self, MerkleBuilder};
+/// let mut builder = MerkleBuilder::new();
+/// builder.push_leaf(b"one");
+/// builder.push_leaf(b"two");
+/// builder.push_leaf(b"three");
+/// assert_eq!(builder.finish(),
+///            sha256::merkle_root(&[sha256::hash(b"one"),
+///                                  sha256::hash(b"two"),
+///                                  sha256::hash(b"three")]));
+/// ```
+#[derive(Clone)]
+pub struct MerkleBuilder {
+    /// Roots of complete subtrees, largest (leftmost) first
+    stack: [[u8; HASHBYTES]; 64],
+    depth: usize,
+    leaves: u64,
+}
+
+#[allow(clippy::new_without_default)]
+impl MerkleBuilder {
+    /// Start building a new tree.
+    pub fn new() -> MerkleBuilder {
+        MerkleBuilder {
+            stack: [[0; HASHBYTES]; 64],
+            depth: 0,
+            leaves: 0,
+        }
+    }
+    /// Add a leaf whose hash is `hash(data)`.
+    pub fn push_leaf(&mut self, data: &[u8]) {
+        self.push_leaf_hash(&hash(data))
+    }
+    /// Add a leaf with the given hash.
+    pub fn push_leaf_hash(&mut self, leaf: &[u8; HASHBYTES]) {
+        let mut node = *leaf;
+        // each trailing one bit is a subtree the same size as the one we're
+        // carrying, ready to merge with it
+        let mut n = self.leaves;
+        while n & 1 != 0 {
+            self.depth -= 1;
+            node = merkle_node(&self.stack[self.depth], &node);
+            n >>= 1;
+        }
+        self.stack[self.depth] = node;
+        self.depth += 1;
+        self.leaves = self.leaves.checked_add(1)
+            .expect("too many Merkle leaves");
+    }
+    /// Return how many leaves have been pushed so far.
+    pub fn leaf_count(&self) -> u64 {
+        self.leaves
+    }
+    /// Combine the remaining subtrees, right to left, and return the root.
+    pub fn finish(self) -> [u8; HASHBYTES] {
+        if self.depth == 0 { return hash(&[]) }
+        let mut root = self.stack[self.depth - 1];
+        for subtree in self.stack[.. self.depth - 1].iter().rev() {
+            root = merkle_node(subtree, &root);
+        }
+        root
+    }
+}
+
+/// Hashes a stream in fixed-size chunks, and then hashes the chunk hashes
+/// together. This is a flat, two-level tree, in the spirit of BLAKE3's
+/// chunking, but with plain SHA-256 throughout:
+///
+/// - The input is split into chunks of `chunk_size` bytes. The last chunk may
+///   be shorter. Empty input counts as a single, empty chunk.
+/// - Each chunk's leaf hash is `hash(chunk)`.
+/// - The root is `hash(leaf_0 || leaf_1 || ... || leaf_n)`.
+///
+/// There's no domain separation or length prefix anywhere, so the chunk size
+/// is part of the construction: the same data hashed with different chunk
+/// sizes gives different roots, and you'll need to record the chunk size
+/// alongside the root. (This is not the same tree as
+/// [`merkle_root`](fn.merkle_root.html) builds.)
+///
+/// Only the current chunk and the root are ever being hashed, so this needs
+/// no heap, and its size doesn't depend on the chunk size. `update_with` and
+/// `finish_with` pass each leaf hash, with its index, to a callback as soon
+/// as its chunk is complete, e.g. for storing chunks by their hashes.
+///
+/// ```rust
+/// # use lsx::sha256::{self, TreeHasher};
+/// let mut hasher = TreeHasher::new(4);
+/// hasher.update(b"foob");
+/// hasher.update(b"arbaz");
+/// let mut leaves = Vec::new();
+/// for chunk in &[&b"foob"[..], b"arba", b"z"] {
+///     leaves.extend_from_slice(&sha256::hash(chunk));
+/// }
+/// assert_eq!(hasher.finish(), sha256::hash(&leaves));
+/// ```
+#[derive(Clone)]
+pub struct TreeHasher {
+    chunk_size: usize,
+    /// Hash of the current chunk so far
+    chunk: BufSha256,
+    /// How many bytes of the current chunk we've seen
+    chunk_bytes: usize,
+    /// Hash of the completed leaves
+    root: BufSha256,
+    leaves: u64,
+}
+
+impl TreeHasher {
+    /// Start hashing, with chunks of the given size.
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn new(chunk_size: usize) -> TreeHasher {
+        assert_ne!(chunk_size, 0, "chunk size must not be zero");
+        TreeHasher {
+            chunk_size,
+            chunk: BufSha256::new(),
+            chunk_bytes: 0,
+            root: BufSha256::new(),
+            leaves: 0,
+        }
+    }
+    /// Return the chunk size.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+    /// Return how many leaves have been completed so far.
+    pub fn leaf_count(&self) -> u64 {
+        self.leaves
+    }
+    /// Finish the current chunk, and add its hash to the root.
+    fn finish_leaf<F: FnMut(u64, &[u8; HASHBYTES])>(&mut self,
+                                                    on_leaf: &mut F) {
+        let leaf = core::mem::take(&mut self.chunk).finish(&[]);
+        self.root.update(&leaf[..]);
+        on_leaf(self.leaves, &leaf);
+        self.leaves += 1;
+        self.chunk_bytes = 0;
+    }
+    /// Hash some more data.
+    pub fn update(&mut self, data: &[u8]) {
+        self.update_with(data, |_, _| ())
+    }
+    /// Hash some more data, calling `on_leaf` with the index and hash of
+    /// each leaf that gets completed along the way.
+    ///
+    /// A chunk that ends exactly at the end of `data` isn't completed until
+    /// more data arrives (or until `finish_with`), since it might turn out to
+    /// be the last one.
+    pub fn update_with<F: FnMut(u64, &[u8; HASHBYTES])>(&mut self,
+                                                         mut data: &[u8],
+                                                         mut on_leaf: F) {
+        while !data.is_empty() {
+            if self.chunk_bytes == self.chunk_size {
+                self.finish_leaf(&mut on_leaf);
+            }
+            let n = (self.chunk_size - self.chunk_bytes).min(data.len());
+            self.chunk.update(&data[.. n]);
+            self.chunk_bytes += n;
+            data = &data[n ..];
+        }
+    }
+    /// Finish the last chunk, and return the root hash.
+    pub fn finish(self) -> [u8; HASHBYTES] {
+        self.finish_with(|_, _| ())
+    }
+    /// Finish the last chunk, passing its index and hash to `on_leaf`, and
+    /// return the root hash.
+    pub fn finish_with<F: FnMut(u64, &[u8; HASHBYTES])>(mut self,
+                                                        mut on_leaf: F)
+                                                        -> [u8; HASHBYTES] {
+        self.finish_leaf(&mut on_leaf);
+        core::mem::take(&mut self.root).finish(&[])
+    }
+}
+
+/// Split `data` into records of `record_size` bytes each, and hash each
+/// record. Returns the hash of every record, along with the
+/// [`merkle_root`](fn.merkle_root.html) of those hashes. This way, you can
+/// check individual records, or the whole lot at once.
+///
+/// Panics if `record_size` is zero, or `data.len()` isn't a multiple of it.
+///
+/// Requires the `"alloc"` feature (enabled by `"std"`).
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let (hashes, root) = sha256::hash_records(3, b"foobarbaz");
+/// assert_eq!(hashes, [sha256::hash(b"foo"), sha256::hash(b"bar"),
+///                     sha256::hash(b"baz")]);
+/// assert_eq!(root, sha256::merkle_root(&hashes));
+/// ```
+#[cfg(feature="alloc")]
+pub fn hash_records(record_size: usize, data: &[u8])
+                    -> (alloc::vec::Vec<[u8; HASHBYTES]>, [u8; HASHBYTES]) {
+    assert_ne!(record_size, 0, "record size must not be zero");
+    assert_eq!(data.len() % record_size, 0,
+               "data must be a whole number of records");
+    let hashes: alloc::vec::Vec<_>
+        = data.chunks_exact(record_size).map(hash).collect();
+    let root = merkle_root(&hashes);
+    (hashes, root)
+}
+
+/// Calculate the SHA-256 hash of each of many independent messages, spreading
+/// the work across threads with `rayon`. The result is the same as
+/// `inputs.iter().map(|x| hash(x)).collect()`, in the same order. This only
+/// pays off when there's a lot of work to go around; for a handful of short
+/// messages, the serial version is faster. Requires the `"rayon"` feature.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let hashes = sha256::hash_many(&[b"foo", b"bar", b"baz"]);
+/// assert_eq!(hashes, [sha256::hash(b"foo"), sha256::hash(b"bar"),
+///                     sha256::hash(b"baz")]);
+/// ```
+#[cfg(feature="rayon")]
+pub fn hash_many(inputs: &[&[u8]]) -> alloc::vec::Vec<[u8; HASHBYTES]> {
+    use rayon::prelude::*;
+    inputs.par_iter().map(|x| hash(x)).collect()
+}
+
+/// Calculate the SHA-256 hash of every `u64` in `start .. end`, each as eight
+/// big-endian bytes, one after another. The range is never stored anywhere.
+/// This is handy for generating reproducible test data. An empty range
+/// (including one where `start > end`) hashes like empty data.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let mut bytes = Vec::new();
+/// for x in 5u64 .. 8 { bytes.extend_from_slice(&x.to_be_bytes()) }
+/// assert_eq!(sha256::hash_u64_range(5, 8), sha256::hash(&bytes));
+/// ```
+pub fn hash_u64_range(start: u64, end: u64) -> [u8; HASHBYTES] {
+    let mut hasher = BufSha256::new();
+    for x in start .. end {
+        hasher.write_u64_be(x);
+    }
+    hasher.finish(&[])
+}
+
+/// Compare two hashes (or HMACs) in constant time, i.e. in a way that doesn't
+/// reveal *where* they differ to an attacker who can measure how long it
+/// took. Every byte is always examined, and the differences are combined
+/// without branching. Use this instead of `==` whenever one of the values is
+/// a secret, such as the expected MAC of a message.
+///
+/// ```rust
+/// # use lsx::sha256::{self, HmacSha256};
+/// let expected = HmacSha256::new(b"key").finish(b"message");
+/// let received = HmacSha256::new(b"key").finish(b"message");
+/// assert!(sha256::verify_eq(&expected, &received));
+/// ```
+pub fn verify_eq(a: &[u8; HASHBYTES], b: &[u8; HASHBYTES]) -> bool {
+    let mut diff = 0u8;
+    for (a, b) in a.iter().zip(b.iter()) {
+        diff |= a ^ b;
+    }
+    core::hint::black_box(diff) == 0
+}
+
+/// Compare two hashes and describe how they differ: the index of the first
+/// byte that differs, and how many bytes differ in total. Returns `None` if
+/// they're equal.
+///
+/// This is a debugging aid, e.g. for working out why a test failed. It is
+/// **not** constant-time, and must not be used to check a secret value (like
+/// a MAC) against an attacker-provided one! Use `verify_eq` for that.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let a = sha256::hash(b"abc");
+/// let mut b = a;
+/// b[3] ^= 1;
+/// b[30] ^= 1;
+/// assert_eq!(sha256::digest_diff(&a, &b), Some((3, 2)));
+/// assert_eq!(sha256::digest_diff(&a, &a), None);
+/// ```
+pub fn digest_diff(a: &[u8; HASHBYTES], b: &[u8; HASHBYTES])
+                   -> Option<(usize, usize)> {
+    let first = a.iter().zip(b.iter()).position(|(a, b)| a != b)?;
+    let count = a.iter().zip(b.iter()).filter(|(a, b)| a != b).count();
+    Some((first, count))
+}
+
+/// Hash `data` as if the `HASHBYTES` bytes at `checksum_offset` were zero.
+fn hash_with_hole(data: &[u8], checksum_offset: usize) -> [u8; HASHBYTES] {
+    let mut hasher = BufSha256::new();
+    hasher.update(&data[.. checksum_offset]);
+    hasher.update_zeros(HASHBYTES as u64);
+    hasher.finish(&data[checksum_offset + HASHBYTES ..])
+}
+
+/// Embed a checksum of `data` within `data` itself, the way many file and
+/// firmware image formats do: the `HASHBYTES` bytes at `checksum_offset` are
+/// zeroed, the whole of `data` is hashed, and then the hash is written into
+/// those bytes. Check it with `verify_embedded_checksum`.
+///
+/// Panics if the checksum doesn't fit in `data` at that offset.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let mut image = [0x55u8; 100];
+/// sha256::hash_with_embedded_checksum(&mut image[..], 4);
+/// assert!(sha256::verify_embedded_checksum(&image[..], 4));
+/// image[99] ^= 1;
+/// assert!(!sha256::verify_embedded_checksum(&image[..], 4));
+/// ```
+pub fn hash_with_embedded_checksum(data: &mut [u8], checksum_offset: usize) {
+    let end = checksum_offset.checked_add(HASHBYTES)
+        .expect("checksum offset is too large");
+    assert!(end <= data.len(), "checksum doesn't fit in the data");
+    let hash = hash_with_hole(data, checksum_offset);
+    data[checksum_offset .. end].copy_from_slice(&hash[..]);
+}
+
+/// Check a checksum embedded by `hash_with_embedded_checksum`: hash `data`
+/// with the checksum bytes treated as zero (without modifying it), and
+/// compare the result against the checksum in constant time. Returns `false`
+/// if the checksum doesn't even fit in `data` at that offset.
+pub fn verify_embedded_checksum(data: &[u8], checksum_offset: usize) -> bool {
+    match checksum_offset.checked_add(HASHBYTES) {
+        Some(end) if end <= data.len() => {
+            verify_eq(&hash_with_hole(data, checksum_offset),
+                  array_ref!(data, checksum_offset, HASHBYTES))
+        },
+        _ => false,
+    }
+}
+
+/// Hashes a stream of data and checks it against an expected hash, using a
+/// constant-time comparison at the end.
+///
+/// ```rust
+/// # use lsx::sha256::{self, Verifier};
+/// let mut verifier = Verifier::new(&sha256::hash(b"Hello World!"));
+/// verifier.update(b"Hello ");
+/// verifier.update(b"World!");
+/// assert!(verifier.verify());
+/// ```
+#[derive(Copy,Clone,Debug)]
+pub struct Verifier {
+    hasher: BufSha256,
+    expected: [u8; HASHBYTES],
+}
+
+impl Verifier {
+    /// Start verifying a stream against the given expected hash.
+    pub fn new(expected: &[u8; HASHBYTES]) -> Verifier {
+        Verifier { hasher: BufSha256::new(), expected: *expected }
+    }
+    /// Process some data. You may provide any amount of data you wish.
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+    /// Finish hashing, and return whether the hash matched what was expected.
+    pub fn verify(self) -> bool {
+        verify_eq(&self.hasher.finish(&[]), &self.expected)
+    }
+}
+
+/// Hash `data`, and compare the result to `expected` in constant time (see
+/// `verify_eq`). Returns whether they match.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let expected = sha256::hash(b"Hello World!");
+/// assert!(sha256::verify(b"Hello World!", &expected));
+/// assert!(!sha256::verify(b"Hello World?", &expected));
+/// ```
+pub fn verify(data: &[u8], expected: &[u8; HASHBYTES]) -> bool {
+    verify_eq(&hash(data), expected)
+}
+
+/// Hash the file at the given path, and compare the result to `expected` (in
+/// constant time). Returns `Ok(true)` if it matches, `Ok(false)` if it
+/// doesn't, and `Err` if the file couldn't be read. Requires the `"std"`
+/// feature (enabled by default).
+#[cfg(feature="std")]
+pub fn verify_file<P: AsRef<std::path::Path>>(path: P,
+                                              expected: &[u8; HASHBYTES])
+    -> std::io::Result<bool> {
+    let mut file = std::fs::File::open(path)?;
+    let (hash, _) = hash_reader_and_len(&mut file)?;
+    Ok(verify_eq(&hash, expected))
+}
+
+/// Calculate the SHA-256 hash of a given byte string, and also return its
+/// length. Mostly useful for symmetry with `hash_reader_and_len`.
+pub fn hash_and_len(data: &[u8]) -> ([u8; HASHBYTES], usize) {
+    (hash(data), data.len())
+}
+
+/// Read everything from `reader` until it reaches end-of-file, and return the
+/// SHA-256 hash of everything that was read, along with the number of bytes
+/// that were read. Handy for building length-prefixed frames from data whose
+/// length isn't known in advance. Requires the `"std"` feature (enabled by
+/// default).
+#[cfg(feature="std")]
+pub fn hash_reader_and_len<R: std::io::Read + ?Sized>(reader: &mut R)
+    -> std::io::Result<([u8; HASHBYTES], u64)> {
+    let mut hasher = BufSha256::new();
+    let mut buf = [0u8; 8192];
+    let mut total = 0u64;
+    loop {
+        match reader.read(&mut buf[..]) {
+            Ok(0) => break,
+            Ok(n) => {
+                hasher.update(&buf[..n]);
+                total += n as u64;
+            },
+            Err(x) if x.kind() == std::io::ErrorKind::Interrupted => (),
+            Err(x) => return Err(x),
+        }
+    }
+    Ok((hasher.finish(&[]), total))
+}
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Write a hash as 64 lowercase hex digits (ASCII), with no separators, into
+/// a buffer you provide. No heap required.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let mut hex = [0u8; 64];
+/// sha256::to_hex_into(&sha256::hash(b"abc"), &mut hex);
+/// assert_eq!(&hex[..], &b"ba7816bf8f01cfea414140de5dae2223\
+///                         b00361a396177a9cb410ff61f20015ad"[..]);
+/// ```
+pub fn to_hex_into(hash: &[u8; HASHBYTES], out: &mut [u8; HASHBYTES*2]) {
+    for (byte, out) in hash.iter().zip(out.chunks_exact_mut(2)) {
+        out[0] = HEX_DIGITS[(byte >> 4) as usize];
+        out[1] = HEX_DIGITS[(byte & 15) as usize];
+    }
+}
+
+/// Format a hash as 64 lowercase hex digits, with no separators. Requires
+/// the `"alloc"` feature (enabled by `"std"`).
+///
+/// ```rust
+/// # use lsx::sha256;
+/// assert_eq!(sha256::to_hex(&sha256::hash(b"abc")),
+///            "ba7816bf8f01cfea414140de5dae2223\
+///             b00361a396177a9cb410ff61f20015ad");
+/// ```
+#[cfg(feature="alloc")]
+pub fn to_hex(hash: &[u8; HASHBYTES]) -> alloc::string::String {
+    let mut hex = [0u8; HASHBYTES*2];
+    to_hex_into(hash, &mut hex);
+    hex.iter().map(|&x| x as char).collect()
+}
+
+fn hex_digit_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0' ..= b'9' => Some(digit - b'0'),
+        b'a' ..= b'f' => Some(digit - b'a' + 10),
+        b'A' ..= b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// The error returned by [`from_hex`](fn.from_hex.html) (and by parsing a
+/// `Sha256Hash`).
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct ParseError {
+    position: usize,
+}
+
+impl ParseError {
+    /// The byte offset where things went wrong: the first character that
+    /// isn't a hex digit, the first character past the 64th if the string is
+    /// too long, or the end of the string if it's too short.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "invalid SHA-256 hex string (at position {})",
+               self.position)
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for ParseError {}
+
+/// Parse a hash from exactly 64 hex digits (upper- or lowercase), with no
+/// prefix or separators.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let hash = sha256::hash(b"abc");
+/// assert_eq!(sha256::from_hex("ba7816bf8f01cfea414140de5dae2223\
+///                              b00361a396177a9cb410ff61f20015ad"),
+///            Ok(hash));
+/// let err = sha256::from_hex("ba7816bf8f01cfea414140de5dae2223\
+///                             b00361a396177a9cb410ff61f20015xd");
+/// assert_eq!(err.unwrap_err().position(), 62);
+/// ```
+pub fn from_hex(s: &str) -> Result<[u8; HASHBYTES], ParseError> {
+    let s = s.as_bytes();
+    let mut ret = [0u8; HASHBYTES];
+    for (position, &digit) in s.iter().take(HASHBYTES * 2).enumerate() {
+        let value = hex_digit_value(digit).ok_or(ParseError { position })?;
+        ret[position / 2] |= value << (if position % 2 == 0 { 4 } else { 0 });
+    }
+    if s.len() != HASHBYTES * 2 {
+        return Err(ParseError { position: s.len().min(HASHBYTES * 2) })
+    }
+    Ok(ret)
+}
+
+impl core::str::FromStr for Sha256Hash {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Sha256Hash, ParseError> {
+        from_hex(s).map(Sha256Hash)
+    }
+}
+
+fn parse_hex(s: &str) -> Option<[u8; HASHBYTES]> {
+    from_hex(s).ok()
+}
+
+/// Parse a hash in the common `sha256:<64 hex digits>` format (as used by
+/// Docker and friends). The `sha256:` prefix is optional, so bare hex is also
+/// accepted. Returns `None` if the string is not in either format.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let hash = sha256::hash(b"abc");
+/// assert_eq!(sha256::parse_prefixed("sha256:ba7816bf8f01cfea414140de5dae2223\
+///                                    b00361a396177a9cb410ff61f20015ad"),
+///            Some(hash));
+/// assert_eq!(sha256::parse_prefixed("ba7816bf8f01cfea414140de5dae2223\
+///                                    b00361a396177a9cb410ff61f20015ad"),
+///            Some(hash));
+/// assert_eq!(sha256::parse_prefixed("md5:ba7816bf8f01cfea414140de5dae2223\
+///                                    b00361a396177a9cb410ff61f20015ad"),
+///            None);
+/// ```
+pub fn parse_prefixed(s: &str) -> Option<[u8; HASHBYTES]> {
+    parse_hex(s.strip_prefix("sha256:").unwrap_or(s))
+}
+
+/// Format a hash in the common `sha256:<64 hex digits>` format, with
+/// lowercase hex digits. Requires the `"alloc"` feature (enabled by
+/// `"std"`).
+#[cfg(feature="alloc")]
+pub fn format_prefixed(hash: &[u8; HASHBYTES]) -> alloc::string::String {
+    let mut hex = [0u8; HASHBYTES*2];
+    to_hex_into(hash, &mut hex);
+    let mut ret = alloc::string::String::with_capacity(7 + hex.len());
+    ret.push_str("sha256:");
+    ret.extend(hex.iter().map(|&x| x as char));
+    ret
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::Zeroize for RawSha256 {
+    fn zeroize(&mut self) {
+        self.h.zeroize();
+        self.byte_count.zeroize();
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::Zeroize for BufSha256 {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+        self.buf.zeroize();
+        self.buffered_bytes.zeroize();
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::Zeroize for RawSha224 {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::Zeroize for BufSha224 {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+    }
+}
+
+#[cfg(all(feature="zeroize", feature="debug-capture"))]
+impl<const N: usize> zeroize::Zeroize for CapturingSha256<N> {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+        self.captured.zeroize();
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::Zeroize for Verifier {
+    fn zeroize(&mut self) {
+        self.hasher.zeroize();
+        self.expected.zeroize();
+    }
+}
+
 #[cfg(test)]
 mod tests;
 
-impl std::fmt::Debug for RawSha256 {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Debug for RawSha256 {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(fmt, "RawSha256 {{ ... }}")
     }
 }
 
-impl std::fmt::Debug for BufSha256 {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Debug for BufSha256 {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(fmt, "BufSha256 {{ ... }}")
     }
 }
+
+impl core::fmt::Debug for TreeHasher {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "TreeHasher {{ ... }}")
+    }
+}
+
+impl core::fmt::Debug for RawSha224 {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "RawSha224 {{ ... }}")
+    }
+}
+
+impl core::fmt::Debug for BufSha224 {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "BufSha224 {{ ... }}")
+    }
+}
+
+impl Default for RawSha256 {
+    fn default() -> RawSha256 {
+        RawSha256::new()
+    }
+}
+
+impl Default for BufSha256 {
+    fn default() -> BufSha256 {
+        BufSha256::new()
+    }
+}
+
+impl Default for RawSha224 {
+    fn default() -> RawSha224 {
+        RawSha224::new()
+    }
+}
+
+impl Default for BufSha224 {
+    fn default() -> BufSha224 {
+        BufSha224::new()
+    }
+}
+
+impl core::fmt::Debug for MerkleBuilder {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "MerkleBuilder {{ ... }}")
+    }
+}
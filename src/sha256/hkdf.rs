@@ -0,0 +1,66 @@
+//! [HKDF][1], the HMAC-based key derivation function, with SHA-256, as
+//! specified in [RFC 5869][2].
+//!
+//! [1]: https://en.wikipedia.org/wiki/HKDF
+//! [2]: https://www.rfc-editor.org/rfc/rfc5869
+
+use super::{HmacSha256, HASHBYTES};
+
+/// The error returned by [`hkdf_expand`](fn.hkdf_expand.html) when asked for
+/// more than `255 * HASHBYTES` bytes of output.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct HkdfLengthError;
+
+impl core::fmt::Display for HkdfLengthError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "HKDF output too long (maximum is {} bytes)",
+               255 * HASHBYTES)
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for HkdfLengthError {}
+
+/// The "extract" step of HKDF: concentrate the entropy in some input keying
+/// material (`ikm`) into a pseudorandom key, suitable for `hkdf_expand`. An
+/// empty `salt` is allowed, and is the same as `HASHBYTES` zeroes.
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; HASHBYTES] {
+    // HMAC zero-pads the key anyway, so an empty salt needs no special case
+    HmacSha256::new(salt).finish(ikm)
+}
+
+/// The "expand" step of HKDF: fill `out` with key material derived from the
+/// pseudorandom key `prk` (usually the result of `hkdf_extract`) and the
+/// context string `info`. Different `info` gives unrelated output, so you
+/// can derive several independent keys from one `prk`.
+///
+/// `out` may be up to `255 * HASHBYTES` (8160) bytes long. If it's longer,
+/// returns `Err(HkdfLengthError)`, and leaves `out` alone.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let prk = sha256::hkdf_extract(b"salt", b"shared secret");
+/// let mut encryption_key = [0u8; 32];
+/// let mut mac_key = [0u8; 32];
+/// sha256::hkdf_expand(&prk, b"encryption", &mut encryption_key).unwrap();
+/// sha256::hkdf_expand(&prk, b"authentication", &mut mac_key).unwrap();
+/// assert_ne!(encryption_key, mac_key);
+/// ```
+pub fn hkdf_expand(prk: &[u8], info: &[u8], out: &mut [u8])
+                   -> Result<(), HkdfLengthError> {
+    if out.len() > 255 * HASHBYTES { return Err(HkdfLengthError) }
+    let prf = HmacSha256::new(prk);
+    let mut t = [0u8; HASHBYTES];
+    for (index, chunk) in out.chunks_mut(HASHBYTES).enumerate() {
+        let mut hmac = prf;
+        // T(0) is empty; every later T(i) includes T(i-1)
+        if index > 0 { hmac.update(&t[..]) }
+        hmac.update(info);
+        t = hmac.finish(&[index as u8 + 1]);
+        chunk.copy_from_slice(&t[.. chunk.len()]);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests;
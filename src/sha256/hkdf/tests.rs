@@ -0,0 +1,59 @@
+use super::*;
+use crate::tests::unhex;
+// Test cases 1-3 from RFC 5869 (the SHA-256 ones)
+type Vector = (&'static [u8], &'static [u8], &'static [u8],
+               &'static str, &'static str);
+const VECTORS: &[Vector] = &[
+    (&[0x0b; 22],
+     b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c",
+     b"\xf0\xf1\xf2\xf3\xf4\xf5\xf6\xf7\xf8\xf9",
+     "077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5",
+     "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf\
+      34007208d5b887185865"),
+    (b"\x00\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\
+       \x10\x11\x12\x13\x14\x15\x16\x17\x18\x19\x1a\x1b\x1c\x1d\x1e\x1f\
+       \x20\x21\x22\x23\x24\x25\x26\x27\x28\x29\x2a\x2b\x2c\x2d\x2e\x2f\
+       \x30\x31\x32\x33\x34\x35\x36\x37\x38\x39\x3a\x3b\x3c\x3d\x3e\x3f\
+       \x40\x41\x42\x43\x44\x45\x46\x47\x48\x49\x4a\x4b\x4c\x4d\x4e\x4f",
+     b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f\
+       \x70\x71\x72\x73\x74\x75\x76\x77\x78\x79\x7a\x7b\x7c\x7d\x7e\x7f\
+       \x80\x81\x82\x83\x84\x85\x86\x87\x88\x89\x8a\x8b\x8c\x8d\x8e\x8f\
+       \x90\x91\x92\x93\x94\x95\x96\x97\x98\x99\x9a\x9b\x9c\x9d\x9e\x9f\
+       \xa0\xa1\xa2\xa3\xa4\xa5\xa6\xa7\xa8\xa9\xaa\xab\xac\xad\xae\xaf",
+     b"\xb0\xb1\xb2\xb3\xb4\xb5\xb6\xb7\xb8\xb9\xba\xbb\xbc\xbd\xbe\xbf\
+       \xc0\xc1\xc2\xc3\xc4\xc5\xc6\xc7\xc8\xc9\xca\xcb\xcc\xcd\xce\xcf\
+       \xd0\xd1\xd2\xd3\xd4\xd5\xd6\xd7\xd8\xd9\xda\xdb\xdc\xdd\xde\xdf\
+       \xe0\xe1\xe2\xe3\xe4\xe5\xe6\xe7\xe8\xe9\xea\xeb\xec\xed\xee\xef\
+       \xf0\xf1\xf2\xf3\xf4\xf5\xf6\xf7\xf8\xf9\xfa\xfb\xfc\xfd\xfe\xff",
+     "06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244",
+     "b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c\
+      59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71\
+      cc30c58179ec3e87c14c01d5c1f3434f1d87"),
+    (&[0x0b; 22], b"", b"",
+     "19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04",
+     "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d\
+      9d201395faa4b61a96c8"),
+];
+#[test]
+pub fn rfc5869() {
+    for (ikm, salt, info, prk, okm) in VECTORS {
+        let prk = unhex(prk);
+        let okm = unhex(okm);
+        assert_eq!(hkdf_extract(salt, ikm)[..], prk[..]);
+        let mut out = vec![0u8; okm.len()];
+        hkdf_expand(&prk, info, &mut out).unwrap();
+        assert_eq!(out, okm);
+    }
+}
+#[test]
+pub fn expand_length_limit() {
+    let prk = [0x42; HASHBYTES];
+    let mut out = vec![0xEE; 255 * HASHBYTES + 1];
+    assert_eq!(hkdf_expand(&prk, b"info", &mut out), Err(HkdfLengthError));
+    assert!(out.iter().all(|&x| x == 0xEE));
+    let mut max = vec![0u8; 255 * HASHBYTES];
+    assert_eq!(hkdf_expand(&prk, b"info", &mut max), Ok(()));
+    // a shorter output is a prefix of a longer one
+    assert_eq!(hkdf_expand(&prk, b"info", &mut out[.. 100]), Ok(()));
+    assert_eq!(out[.. 100], max[.. 100]);
+}
@@ -0,0 +1,112 @@
+//! [HMAC][1]-SHA-256, a message authentication code built on SHA-256.
+//!
+//! [1]: https://en.wikipedia.org/wiki/HMAC
+
+use super::{BufSha256, RawSha256, BLOCKBYTES, HASHBYTES};
+
+/// An HMAC-SHA-256 state. Use this to authenticate a message with a secret
+/// key.
+///
+/// ```rust
+/// # use lsx::sha256::HmacSha256;
+/// let mut hmac = HmacSha256::new(b"key");
+/// hmac.update(b"The quick brown fox ");
+/// assert_eq!(hmac.finish(b"jumps over the lazy dog"),
+///            [0xf7,0xbc,0x83,0xf4,0x30,0x53,0x84,0x24,0xb1,0x32,0x98,0xe6,
+///             0xaa,0x6f,0xb1,0x43,0xef,0x4d,0x59,0xa1,0x49,0x46,0x17,0x59,
+///             0x97,0x47,0x9d,0xbc,0x2d,0x1a,0x3c,0xd8]);
+/// ```
+///
+/// The state is derived from the key, so it's as sensitive as the key. With
+/// the `"zeroize"` feature, `.zeroize()` wipes it, but dropping an
+/// `HmacSha256` doesn't; call it yourself, or use `zeroize::Zeroizing`.
+#[derive(Copy,Clone)]
+pub struct HmacSha256 {
+    inner: BufSha256,
+    outer: RawSha256,
+}
+
+impl HmacSha256 {
+    /// Start a new HMAC with the given key. The key may be any length, but
+    /// keys longer than `BLOCKBYTES` are hashed down to `HASHBYTES` first, and
+    /// so they are no stronger than a `HASHBYTES` key.
+    pub fn new(key: &[u8]) -> HmacSha256 {
+        let mut pad = [0u8; BLOCKBYTES];
+        if key.len() > BLOCKBYTES {
+            pad[.. HASHBYTES].copy_from_slice(&super::hash(key)[..]);
+        }
+        else {
+            pad[.. key.len()].copy_from_slice(key);
+        }
+        for x in pad.iter_mut() { *x ^= 0x36 }
+        let mut inner = BufSha256::new();
+        inner.update(&pad[..]);
+        for x in pad.iter_mut() { *x ^= 0x36 ^ 0x5c }
+        let mut outer = RawSha256::new();
+        outer.update(&pad[..]);
+        HmacSha256 { inner, outer }
+    }
+    /// Process some data. You may provide any amount of data you wish.
+    pub fn update(&mut self, data: &[u8]) {
+        self.inner.update(data);
+    }
+    /// Process any remaining data and produce a finished MAC.
+    pub fn finish(self, data: &[u8]) -> [u8; HASHBYTES] {
+        let inner = self.inner.finish(data);
+        self.outer.finish(&inner[..])
+    }
+}
+
+impl core::fmt::Debug for HmacSha256 {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "HmacSha256 {{ ... }}")
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::Zeroize for HmacSha256 {
+    fn zeroize(&mut self) {
+        self.inner.zeroize();
+        self.outer.zeroize();
+    }
+}
+
+/// Calculate the HMAC-SHA-256 of a message, all at once. This is the HMAC
+/// equivalent of [`hash`](fn.hash.html).
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let mac = sha256::hmac(b"key",
+///                        b"The quick brown fox jumps over the lazy dog");
+/// assert_eq!(mac,
+///            [0xf7,0xbc,0x83,0xf4,0x30,0x53,0x84,0x24,0xb1,0x32,0x98,0xe6,
+///             0xaa,0x6f,0xb1,0x43,0xef,0x4d,0x59,0xa1,0x49,0x46,0x17,0x59,
+///             0x97,0x47,0x9d,0xbc,0x2d,0x1a,0x3c,0xd8]);
+/// ```
+pub fn hmac(key: &[u8], message: &[u8]) -> [u8; HASHBYTES] {
+    HmacSha256::new(key).finish(message)
+}
+
+/// Calculate the HMAC-SHA-256 of the concatenation of several byte strings,
+/// without having to concatenate them first. Useful for authenticating (for
+/// example) a header and a body that live in separate buffers.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let header = b"header:";
+/// let body = b"The quick brown fox jumps over the lazy dog";
+/// let mac = sha256::hmac_parts(b"key", &[header, body]);
+/// let mut hmac = sha256::HmacSha256::new(b"key");
+/// hmac.update(b"header:The quick brown fox jumps over the lazy dog");
+/// assert_eq!(mac, hmac.finish(&[]));
+/// ```
+pub fn hmac_parts(key: &[u8], parts: &[&[u8]]) -> [u8; HASHBYTES] {
+    let mut hmac = HmacSha256::new(key);
+    for part in parts {
+        hmac.update(part);
+    }
+    hmac.finish(&[])
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,48 @@
+use super::*;
+// Test cases 1-4, 6 and 7 from RFC 4231 (case 5 is truncated; see below)
+const RFC4231: &[(&[u8], &[u8], [u8; HASHBYTES])] = &[
+    (&[0x0b; 20], b"Hi There", [0xb0,0x34,0x4c,0x61,0xd8,0xdb,0x38,0x53,0x5c,0xa8,0xaf,0xce,0xaf,0x0b,0xf1,0x2b,0x88,0x1d,0xc2,0x00,0xc9,0x83,0x3d,0xa7,0x26,0xe9,0x37,0x6c,0x2e,0x32,0xcf,0xf7]),
+    (b"Jefe", b"what do ya want for nothing?", [0x5b,0xdc,0xc1,0x46,0xbf,0x60,0x75,0x4e,0x6a,0x04,0x24,0x26,0x08,0x95,0x75,0xc7,0x5a,0x00,0x3f,0x08,0x9d,0x27,0x39,0x83,0x9d,0xec,0x58,0xb9,0x64,0xec,0x38,0x43]),
+    (&[0xaa; 20], &[0xdd; 50], [0x77,0x3e,0xa9,0x1e,0x36,0x80,0x0e,0x46,0x85,0x4d,0xb8,0xeb,0xd0,0x91,0x81,0xa7,0x29,0x59,0x09,0x8b,0x3e,0xf8,0xc1,0x22,0xd9,0x63,0x55,0x14,0xce,0xd5,0x65,0xfe]),
+    (b"\x01\x02\x03\x04\x05\x06\x07\x08\x09\x0a\x0b\x0c\x0d\x0e\x0f\x10\x11\x12\x13\x14\x15\x16\x17\x18\x19", &[0xcd; 50], [0x82,0x55,0x8a,0x38,0x9a,0x44,0x3c,0x0e,0xa4,0xcc,0x81,0x98,0x99,0xf2,0x08,0x3a,0x85,0xf0,0xfa,0xa3,0xe5,0x78,0xf8,0x07,0x7a,0x2e,0x3f,0xf4,0x67,0x29,0x66,0x5b]),
+    (&[0xaa; 131], b"Test Using Larger Than Block-Size Key - Hash Key First", [0x60,0xe4,0x31,0x59,0x1e,0xe0,0xb6,0x7f,0x0d,0x8a,0x26,0xaa,0xcb,0xf5,0xb7,0x7f,0x8e,0x0b,0xc6,0x21,0x37,0x28,0xc5,0x14,0x05,0x46,0x04,0x0f,0x0e,0xe3,0x7f,0x54]),
+    (&[0xaa; 131], b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.", [0x9b,0x09,0xff,0xa7,0x1b,0x94,0x2f,0xcb,0x27,0x63,0x5f,0xbc,0xd5,0xb0,0xe9,0x44,0xbf,0xdc,0x63,0x64,0x4f,0x07,0x13,0x93,0x8a,0x7f,0x51,0x53,0x5c,0x3a,0x35,0xe2]),
+];
+
+#[test]
+pub fn parts() {
+    const ANSWER: [u8; HASHBYTES] = [
+        0xbd,0x7e,0x80,0x73,0x4a,0x30,0x60,0x52,0xa8,0xbd,0x78,0x87,0x34,0xe8,
+        0xe4,0xa0,0x58,0xb4,0x43,0x05,0x5d,0x9e,0x5a,0x3b,0x76,0xa3,0x57,0x87,
+        0x84,0x54,0x94,0x52,
+    ];
+    let parts: [&[u8]; 3] = [b"header:", b"",
+                             b"The quick brown fox jumps over the lazy dog"];
+    assert_eq!(hmac_parts(b"key", &parts), ANSWER);
+    let mut hmac = HmacSha256::new(b"key");
+    hmac.update(&parts.concat());
+    assert_eq!(hmac.finish(&[]), ANSWER);
+}
+#[test]
+pub fn rfc4231() {
+    for (key, data, answer) in RFC4231 {
+        assert_eq!(HmacSha256::new(key).finish(data), *answer);
+        for chunksize in &[1, 13, 64, 65] {
+            let mut hmac = HmacSha256::new(key);
+            for chunk in data.chunks(*chunksize) { hmac.update(chunk) }
+            assert_eq!(hmac.finish(&[]), *answer);
+        }
+    }
+}
+#[test]
+pub fn one_shot() {
+    for (key, data, answer) in RFC4231 {
+        assert_eq!(hmac(key, data), *answer);
+    }
+}
+#[test]
+pub fn rfc4231_truncated() {
+    // test case 5, which only specifies the first 128 bits of the output
+    let mac = HmacSha256::new(&[0x0c; 20]).finish(b"Test With Truncation");
+    assert_eq!(mac[.. 16], [0xa3,0xb6,0x16,0x74,0x73,0x10,0x0e,0xe0,0x6e,0x0c,0x79,0x6c,0x29,0x55,0x55,0x2b]);
+}
@@ -0,0 +1,46 @@
+//! [PBKDF2][1]-HMAC-SHA-256, a way to turn a password into a key, as
+//! specified in [RFC 8018][2].
+//!
+//! [1]: https://en.wikipedia.org/wiki/PBKDF2
+//! [2]: https://www.rfc-editor.org/rfc/rfc8018#section-5.2
+
+use core::convert::TryFrom;
+use super::{HmacSha256, HASHBYTES};
+
+/// Derive a key (or several) from a password, using PBKDF2 with HMAC-SHA-256
+/// and the given salt and iteration count, filling all of `out`. `out` may
+/// be any length; if it isn't a multiple of `HASHBYTES`, the last block is
+/// truncated.
+///
+/// Use a random salt, unique to each password, and as many iterations as you
+/// can stand. Panics if `iterations` is zero.
+///
+/// ```rust
+/// # use lsx::sha256;
+/// let mut key = [0u8; 32];
+/// sha256::pbkdf2(b"password", b"salt", 1, &mut key);
+/// assert_eq!(key,
+///            [0x12,0x0f,0xb6,0xcf,0xfc,0xf8,0xb3,0x2c,0x43,0xe7,0x22,0x52,
+///             0x56,0xc4,0xf8,0x37,0xa8,0x65,0x48,0xc9,0x2c,0xcc,0x35,0x48,
+///             0x08,0x05,0x98,0x7c,0xb7,0x0b,0xe1,0x7b]);
+/// ```
+pub fn pbkdf2(password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+    assert!(iterations > 0, "PBKDF2 needs at least one iteration");
+    let prf = HmacSha256::new(password);
+    for (index, chunk) in out.chunks_mut(HASHBYTES).enumerate() {
+        let index = u32::try_from(index + 1)
+            .expect("PBKDF2 output is too long");
+        let mut hmac = prf;
+        hmac.update(salt);
+        let mut u = hmac.finish(&index.to_be_bytes()[..]);
+        let mut t = u;
+        for _ in 1 .. iterations {
+            u = prf.finish(&u[..]);
+            for (t, u) in t.iter_mut().zip(u.iter()) { *t ^= u }
+        }
+        chunk.copy_from_slice(&t[.. chunk.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests;
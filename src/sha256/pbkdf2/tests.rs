@@ -0,0 +1,42 @@
+use super::*;
+// RFC 6070's test cases, with SHA-256 instead of SHA-1, and the first test
+// vector from RFC 7914
+type Vector = (&'static [u8], &'static [u8], u32, &'static [u8]);
+const VECTORS: &[Vector] = &[
+    (b"password", b"salt", 1, &[0x12,0x0f,0xb6,0xcf,0xfc,0xf8,0xb3,0x2c,0x43,0xe7,0x22,0x52,0x56,0xc4,0xf8,0x37,0xa8,0x65,0x48,0xc9,0x2c,0xcc,0x35,0x48,0x08,0x05,0x98,0x7c,0xb7,0x0b,0xe1,0x7b]),
+    (b"password", b"salt", 2, &[0xae,0x4d,0x0c,0x95,0xaf,0x6b,0x46,0xd3,0x2d,0x0a,0xdf,0xf9,0x28,0xf0,0x6d,0xd0,0x2a,0x30,0x3f,0x8e,0xf3,0xc2,0x51,0xdf,0xd6,0xe2,0xd8,0x5a,0x95,0x47,0x4c,0x43]),
+    (b"password", b"salt", 4096, &[0xc5,0xe4,0x78,0xd5,0x92,0x88,0xc8,0x41,0xaa,0x53,0x0d,0xb6,0x84,0x5c,0x4c,0x8d,0x96,0x28,0x93,0xa0,0x01,0xce,0x4e,0x11,0xa4,0x96,0x38,0x73,0xaa,0x98,0x13,0x4a]),
+    (b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, &[0x34,0x8c,0x89,0xdb,0xcb,0xd3,0x2b,0x2f,0x32,0xd8,0x14,0xb8,0x11,0x6e,0x84,0xcf,0x2b,0x17,0x34,0x7e,0xbc,0x18,0x00,0x18,0x1c,0x4e,0x2a,0x1f,0xb8,0xdd,0x53,0xe1,0xc6,0x35,0x51,0x8c,0x7d,0xac,0x47,0xe9]),
+    (b"pass\0word", b"sa\0lt", 4096, &[0x89,0xb6,0x9d,0x05,0x16,0xf8,0x29,0x89,0x3c,0x69,0x62,0x26,0x65,0x0a,0x86,0x87]),
+    (b"passwd", b"salt", 1, &[0x55,0xac,0x04,0x6e,0x56,0xe3,0x08,0x9f,0xec,0x16,0x91,0xc2,0x25,0x44,0xb6,0x05,0xf9,0x41,0x85,0x21,0x6d,0xde,0x04,0x65,0xe6,0x8b,0x9d,0x57,0xc2,0x0d,0xac,0xbc,0x49,0xca,0x9c,0xcc,0xf1,0x79,0xb6,0x45,0x99,0x16,0x64,0xb3,0x9d,0x77,0xef,0x31,0x7c,0x71,0xb8,0x45,0xb1,0xe3,0x0b,0xd5,0x09,0x11,0x20,0x41,0xd3,0xa1,0x97,0x83]),
+];
+#[test]
+pub fn vectors() {
+    for (password, salt, iterations, answer) in VECTORS {
+        let mut out = vec![0u8; answer.len()];
+        pbkdf2(password, salt, *iterations, &mut out);
+        assert_eq!(&out[..], *answer);
+    }
+}
+#[test]
+pub fn truncation() {
+    let mut long = [0u8; 100];
+    pbkdf2(b"password", b"salt", 3, &mut long);
+    for len in 0 .. long.len() {
+        let mut out = vec![0u8; len];
+        pbkdf2(b"password", b"salt", 3, &mut out);
+        assert_eq!(out[..], long[.. len]);
+    }
+}
+#[test]
+#[should_panic]
+pub fn zero_iterations() {
+    pbkdf2(b"password", b"salt", 0, &mut [0; 32]);
+}
+#[test]
+#[cfg(feature="slow-tests")]
+pub fn sixteen_million_iterations() {
+    let mut out = [0u8; 32];
+    pbkdf2(b"password", b"salt", 16777216, &mut out);
+    assert_eq!(out, [0xcf,0x81,0xc6,0x6f,0xe8,0xcf,0xc0,0x4d,0x1f,0x31,0xec,0xb6,0x5d,0xab,0x40,0x89,0xf7,0xf1,0x79,0xe8,0x9b,0x3b,0x0b,0xcb,0x17,0xad,0x10,0xe3,0xac,0x6e,0xba,0x46]);
+}
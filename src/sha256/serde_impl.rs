@@ -0,0 +1,125 @@
+//! `serde` support for `RawSha256` and `BufSha256`, for checkpointing a hash
+//! in progress.
+//!
+//! A `BufSha256` is serialized as its state words, its byte count, and only
+//! the bytes that are actually sitting in the buffer. Both types reject, on
+//! deserialization, anything that couldn't have come from a real hasher.
+
+use super::*;
+
+use core::fmt;
+use serde::{Serialize, Serializer, Deserialize, Deserializer,
+            de::{self, Visitor, SeqAccess}, ser::SerializeStruct};
+
+#[derive(Deserialize)]
+#[serde(rename="RawSha256")]
+struct RawState {
+    h: [u32; 8],
+    byte_count: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename="BufSha256")]
+struct BufState {
+    h: [u32; 8],
+    byte_count: u64,
+    buffered: Buffered,
+}
+
+/// The partial block held by a `BufSha256`. Always less than a block.
+struct Buffered([u8; BLOCKBYTES], usize);
+
+struct Bytes<'a>(&'a [u8]);
+
+// (`is_multiple_of` is only stable since Rust 1.87)
+#[allow(clippy::manual_is_multiple_of)]
+fn check_byte_count<E: de::Error>(byte_count: u64) -> Result<(), E> {
+    if byte_count % BLOCKBYTES as u64 == 0 { Ok(()) }
+    else {
+        Err(E::custom("byte_count is not a multiple of BLOCKBYTES"))
+    }
+}
+
+impl Serialize for Bytes<'_> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Buffered {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Buffered, D::Error> {
+        d.deserialize_bytes(BufferedVisitor)
+    }
+}
+
+struct BufferedVisitor;
+
+impl<'de> Visitor<'de> for BufferedVisitor {
+    type Value = Buffered;
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "fewer than {} bytes", BLOCKBYTES)
+    }
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Buffered, E> {
+        if v.len() >= BLOCKBYTES {
+            return Err(E::invalid_length(v.len(), &self))
+        }
+        let mut buf = [0u8; BLOCKBYTES];
+        buf[..v.len()].copy_from_slice(v);
+        Ok(Buffered(buf, v.len()))
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A)
+                                    -> Result<Buffered, A::Error> {
+        let mut buf = [0u8; BLOCKBYTES];
+        let mut len = 0;
+        while let Some(byte) = seq.next_element()? {
+            if len == BLOCKBYTES - 1 {
+                return Err(de::Error::invalid_length(BLOCKBYTES, &self))
+            }
+            buf[len] = byte;
+            len += 1;
+        }
+        Ok(Buffered(buf, len))
+    }
+}
+
+impl Serialize for RawSha256 {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut st = s.serialize_struct("RawSha256", 2)?;
+        st.serialize_field("h", &self.h)?;
+        st.serialize_field("byte_count", &self.byte_count)?;
+        st.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for RawSha256 {
+    fn deserialize<D: Deserializer<'de>>(d: D)
+                                         -> Result<RawSha256, D::Error> {
+        let RawState { h, byte_count } = RawState::deserialize(d)?;
+        check_byte_count(byte_count)?;
+        Ok(RawSha256 { h, byte_count })
+    }
+}
+
+impl Serialize for BufSha256 {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let buffered = &self.buf[..self.buffered_bytes as usize];
+        let mut st = s.serialize_struct("BufSha256", 3)?;
+        st.serialize_field("h", &self.inner.h)?;
+        st.serialize_field("byte_count", &self.inner.byte_count)?;
+        st.serialize_field("buffered", &Bytes(buffered))?;
+        st.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for BufSha256 {
+    fn deserialize<D: Deserializer<'de>>(d: D)
+                                         -> Result<BufSha256, D::Error> {
+        let BufState { h, byte_count, buffered } = BufState::deserialize(d)?;
+        check_byte_count(byte_count)?;
+        Ok(BufSha256 {
+            inner: RawSha256 { h, byte_count },
+            buf: buffered.0,
+            buffered_bytes: buffered.1 as u32,
+        })
+    }
+}
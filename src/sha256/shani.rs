@@ -0,0 +1,116 @@
+//! The SHA-256 compression function using the x86 SHA extensions ("SHA-NI"),
+//! for CPUs that have them. Enabled by the `"sha-ni"` feature.
+//!
+//! This is the only `unsafe` code in the crate. The intrinsics can only be
+//! called once we know the CPU supports them, so `compress` checks for
+//! itself rather than trusting its caller to have done so; nothing outside
+//! this module can reach the `unsafe` parts with an unsupported CPU.
+
+#![allow(unsafe_code)]
+
+use super::{BLOCKBYTES, Backend, backend};
+
+#[cfg(target_arch="x86_64")]
+use super::K;
+#[cfg(target_arch="x86_64")]
+use core::arch::x86_64::*;
+
+/// Whether the CPU has everything `compress_blocks` needs.
+#[cfg(target_arch="x86_64")]
+fn cpu_supported() -> bool {
+    std::is_x86_feature_detected!("sha")
+        && std::is_x86_feature_detected!("sse2")
+        && std::is_x86_feature_detected!("ssse3")
+        && std::is_x86_feature_detected!("sse4.1")
+}
+
+#[cfg(not(target_arch="x86_64"))]
+fn cpu_supported() -> bool { false }
+
+/// Run the compression function over `data` (a whole number of blocks),
+/// updating `h`, if the SHA extensions are available and the current
+/// backend allows their use. Returns `false`, without touching `h`, if they
+/// aren't.
+pub(super) fn compress(h: &mut [u32; 8], data: &[u8]) -> bool {
+    debug_assert_eq!(data.len() % BLOCKBYTES, 0);
+    if backend() == Backend::Scalar || !cpu_supported() { return false }
+    #[cfg(target_arch="x86_64")]
+    // SAFETY: `cpu_supported` just checked for every feature this needs.
+    unsafe { compress_blocks(h, data) }
+    true
+}
+
+/// Run four rounds, given four words of the message schedule and the index
+/// of the first round.
+#[cfg(target_arch="x86_64")]
+macro_rules! rounds4 {
+    ($abef:ident, $cdgh:ident, $w:expr, $n:expr) => {{
+        let k = _mm_loadu_si128(K.as_ptr().add($n * 4) as *const __m128i);
+        let wk = _mm_add_epi32($w, k);
+        $cdgh = _mm_sha256rnds2_epu32($cdgh, $abef, wk);
+        $abef = _mm_sha256rnds2_epu32($abef, $cdgh,
+                                      _mm_shuffle_epi32(wk, 0x0E));
+    }};
+}
+
+/// Compute the next four words of the message schedule, given the previous
+/// sixteen (oldest first), then run four rounds with them.
+#[cfg(target_arch="x86_64")]
+macro_rules! schedule_rounds4 {
+    ($abef:ident, $cdgh:ident, $w0:ident, $w1:ident, $w2:ident, $w3:ident,
+     $out:ident, $n:expr) => {{
+        let t = _mm_add_epi32(_mm_sha256msg1_epu32($w0, $w1),
+                              _mm_alignr_epi8($w3, $w2, 4));
+        $out = _mm_sha256msg2_epu32(t, $w3);
+        rounds4!($abef, $cdgh, $out, $n);
+    }};
+}
+
+#[cfg(target_arch="x86_64")]
+#[target_feature(enable="sha,sse2,ssse3,sse4.1")]
+unsafe fn compress_blocks(h: &mut [u32; 8], data: &[u8]) {
+    // reverses the bytes of each word, to load them big-endian
+    let bswap = _mm_set_epi64x(0x0C0D_0E0F_0809_0A0B,
+                               0x0405_0607_0001_0203);
+    // The SHA instructions want the state as ABEF and CDGH, not ABCD and
+    // EFGH.
+    let dcba = _mm_loadu_si128(h.as_ptr() as *const __m128i);
+    let hgfe = _mm_loadu_si128(h.as_ptr().add(4) as *const __m128i);
+    let cdab = _mm_shuffle_epi32(dcba, 0xB1);
+    let efgh = _mm_shuffle_epi32(hgfe, 0x1B);
+    let mut abef = _mm_alignr_epi8(cdab, efgh, 8);
+    let mut cdgh = _mm_blend_epi16(efgh, cdab, 0xF0);
+    for block in data.chunks_exact(BLOCKBYTES) {
+        let (abef_in, cdgh_in) = (abef, cdgh);
+        let p = block.as_ptr() as *const __m128i;
+        let mut w0 = _mm_shuffle_epi8(_mm_loadu_si128(p), bswap);
+        let mut w1 = _mm_shuffle_epi8(_mm_loadu_si128(p.add(1)), bswap);
+        let mut w2 = _mm_shuffle_epi8(_mm_loadu_si128(p.add(2)), bswap);
+        let mut w3 = _mm_shuffle_epi8(_mm_loadu_si128(p.add(3)), bswap);
+        let mut w4;
+        rounds4!(abef, cdgh, w0, 0);
+        rounds4!(abef, cdgh, w1, 1);
+        rounds4!(abef, cdgh, w2, 2);
+        rounds4!(abef, cdgh, w3, 3);
+        schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 4);
+        schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 5);
+        schedule_rounds4!(abef, cdgh, w2, w3, w4, w0, w1, 6);
+        schedule_rounds4!(abef, cdgh, w3, w4, w0, w1, w2, 7);
+        schedule_rounds4!(abef, cdgh, w4, w0, w1, w2, w3, 8);
+        schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 9);
+        schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 10);
+        schedule_rounds4!(abef, cdgh, w2, w3, w4, w0, w1, 11);
+        schedule_rounds4!(abef, cdgh, w3, w4, w0, w1, w2, 12);
+        schedule_rounds4!(abef, cdgh, w4, w0, w1, w2, w3, 13);
+        schedule_rounds4!(abef, cdgh, w0, w1, w2, w3, w4, 14);
+        schedule_rounds4!(abef, cdgh, w1, w2, w3, w4, w0, 15);
+        abef = _mm_add_epi32(abef, abef_in);
+        cdgh = _mm_add_epi32(cdgh, cdgh_in);
+    }
+    let feba = _mm_shuffle_epi32(abef, 0x1B);
+    let dchg = _mm_shuffle_epi32(cdgh, 0xB1);
+    let dcba = _mm_blend_epi16(feba, dchg, 0xF0);
+    let hgef = _mm_alignr_epi8(dchg, feba, 8);
+    _mm_storeu_si128(h.as_mut_ptr() as *mut __m128i, dcba);
+    _mm_storeu_si128(h.as_mut_ptr().add(4) as *mut __m128i, hgef);
+}
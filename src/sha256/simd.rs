@@ -0,0 +1,54 @@
+//! A message schedule that computes four words at a time. It's written with
+//! plain arrays of four lanes rather than intrinsics (this crate has no
+//! `unsafe`), leaving it to the compiler to map them onto SSE2, NEON, or
+//! whatever the target has. Whether that beats the scalar schedule depends
+//! on the target and the compiler; benchmark before you enable it. Enabled
+//! by the `"simd"` feature.
+
+use super::BLOCKBYTES;
+
+type Lanes = [u32; 4];
+
+#[inline(always)]
+fn add(a: Lanes, b: Lanes) -> Lanes {
+    [a[0].wrapping_add(b[0]), a[1].wrapping_add(b[1]),
+     a[2].wrapping_add(b[2]), a[3].wrapping_add(b[3])]
+}
+
+#[inline(always)]
+fn sigma0(x: Lanes) -> Lanes {
+    let r7 = [x[0].rotate_right(7), x[1].rotate_right(7),
+              x[2].rotate_right(7), x[3].rotate_right(7)];
+    let r18 = [x[0].rotate_right(18), x[1].rotate_right(18),
+               x[2].rotate_right(18), x[3].rotate_right(18)];
+    [r7[0] ^ r18[0] ^ (x[0] >> 3), r7[1] ^ r18[1] ^ (x[1] >> 3),
+     r7[2] ^ r18[2] ^ (x[2] >> 3), r7[3] ^ r18[3] ^ (x[3] >> 3)]
+}
+
+#[inline(always)]
+fn sigma1(x: u32) -> u32 {
+    x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
+}
+
+/// Expand a block into the 64-word message schedule.
+pub(super) fn schedule(input: &[u8; BLOCKBYTES]) -> [u32; 64] {
+    let mut w = [0u32; 64];
+    for (w, word) in w.iter_mut().zip(input.chunks_exact(4)) {
+        *w = u32::from_be_bytes(*array_ref!(word, 0, 4));
+    }
+    for n in (16 .. 64).step_by(4) {
+        // everything except the s1 term only looks back 7 or more words, so
+        // it can be done four at a time
+        let mut t = add(add(*array_ref!(w, n-16, 4),
+                            sigma0(*array_ref!(w, n-15, 4))),
+                        *array_ref!(w, n-7, 4));
+        // the s1 term looks back two words, so the last two words need the
+        // first two to be finished
+        t[0] = t[0].wrapping_add(sigma1(w[n-2]));
+        t[1] = t[1].wrapping_add(sigma1(w[n-1]));
+        t[2] = t[2].wrapping_add(sigma1(t[0]));
+        t[3] = t[3].wrapping_add(sigma1(t[1]));
+        *array_mut_ref!(w, n, 4) = t;
+    }
+    w
+}
@@ -1,5 +1,8 @@
 use super::*;
 const KNOWN_ANSWERS: &[(&[u8], [u8; HASHBYTES])] = &[
+    // Known answers from FIPS 180-4 (and its accompanying examples)
+    (b"abc", [0xba,0x78,0x16,0xbf,0x8f,0x01,0xcf,0xea,0x41,0x41,0x40,0xde,0x5d,0xae,0x22,0x23,0xb0,0x03,0x61,0xa3,0x96,0x17,0x7a,0x9c,0xb4,0x10,0xff,0x61,0xf2,0x00,0x15,0xad]),
+    (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", [0x24,0x8d,0x6a,0x61,0xd2,0x06,0x38,0xb8,0xe5,0xc0,0x26,0x93,0x0c,0x3e,0x60,0x39,0xa3,0x3c,0xe4,0x59,0x64,0xff,0x21,0x67,0xf6,0xec,0xed,0xd4,0x19,0xdb,0x06,0xc1]),
     // Known answers from Wikipedia - http://en.wikipedia.org/wiki/SHA-2
     (b"", [0xe3,0xb0,0xc4,0x42,0x98,0xfc,0x1c,0x14,0x9a,0xfb,0xf4,0xc8,0x99,0x6f,0xb9,0x24,0x27,0xae,0x41,0xe4,0x64,0x9b,0x93,0x4c,0xa4,0x95,0x99,0x1b,0x78,0x52,0xb8,0x55]),
     (b"The quick brown fox jumps over the lazy dog", [0xd7,0xa8,0xfb,0xb3,0x07,0xd7,0x80,0x94,0x69,0xca,0x9a,0xbc,0xb0,0x08,0x2e,0x4f,0x8d,0x56,0x51,0xe4,0x6d,0x3c,0xdb,0x76,0x2d,0x02,0xd0,0xbf,0x37,0xc9,0xe5,0x92]),
@@ -29,3 +32,860 @@ pub fn known_answers_buf() {
         }
     }
 }
+#[test]
+#[cfg(feature="std")]
+pub fn finish_to_writer() {
+    for (data, answer) in KNOWN_ANSWERS {
+        let mut out = Vec::new();
+        RawSha256::new().finish_to_writer(data, &mut out).unwrap();
+        assert_eq!(&out[..], &answer[..]);
+        assert_eq!(&out[..], &RawSha256::new().finish(data)[..]);
+    }
+}
+#[test]
+#[cfg(feature="slow-tests")]
+pub fn million_a() {
+    // Known answer from FIPS 180-4
+    const ANSWER: [u8; HASHBYTES] = [0xcd,0xc7,0x6e,0x5c,0x99,0x14,0xfb,0x92,0x81,0xa1,0xc7,0xe2,0x84,0xd7,0x3e,0x67,0xf1,0x80,0x9a,0x48,0xa4,0x97,0x20,0x0e,0x04,0x6d,0x39,0xcc,0xc7,0x11,0x2c,0xd0];
+    let data = [b'a'; 1000];
+    let mut hasher = BufSha256::new();
+    for _ in 0 .. 1000 {
+        hasher.update(&data[..]);
+    }
+    assert_eq!(hasher.finish(&[]), ANSWER);
+    let data = vec![b'a'; 1000000];
+    assert_eq!(hash(&data[..]), ANSWER);
+}
+#[test]
+pub fn write_words() {
+    const LAYOUT: &[u8] = &[
+        0x12,0x34,0x56,0x78,
+        0x78,0x56,0x34,0x12,
+        0x01,0x23,0x45,0x67,0x89,0xab,0xcd,0xef,
+        0xef,0xcd,0xab,0x89,0x67,0x45,0x23,0x01,
+    ];
+    let mut hasher = BufSha256::new();
+    hasher.write_u32_be(0x12345678);
+    hasher.write_u32_le(0x12345678);
+    hasher.write_u64_be(0x0123456789abcdef);
+    hasher.write_u64_le(0x0123456789abcdef);
+    assert_eq!(hasher.finish(&[]), hash(LAYOUT));
+}
+#[test]
+pub fn hash_with_prefix_matches() {
+    for (data, answer) in KNOWN_ANSWERS {
+        let (hash, prefix) = hash_with_prefix(data);
+        assert_eq!(hash, *answer);
+        assert_eq!(prefix.to_be_bytes(), answer[..4]);
+    }
+}
+#[test]
+pub fn update_blockwise() {
+    for chunksize in &[1, 7, 64, 67, 200] {
+        for (data, answer) in KNOWN_ANSWERS {
+            let mut hasher = BufSha256::new();
+            let mut seen = Vec::new();
+            for chunk in data.chunks(*chunksize) {
+                hasher.update_blockwise(chunk, |block| {
+                    seen.extend_from_slice(&block[..]);
+                });
+            }
+            assert_eq!(seen.len() % BLOCKBYTES, 0);
+            let buffered = hasher.buffered_bytes as usize;
+            seen.extend_from_slice(&hasher.buf[.. buffered]);
+            assert_eq!(&seen[..], *data);
+            assert_eq!(hasher.finish(&[]), *answer);
+        }
+    }
+}
+#[test]
+#[cfg(feature="alloc")]
+pub fn prefixed_round_trip() {
+    for (_, answer) in KNOWN_ANSWERS {
+        let formatted = format_prefixed(answer);
+        assert!(formatted.starts_with("sha256:"));
+        assert_eq!(formatted.len(), 7 + HASHBYTES*2);
+        assert_eq!(parse_prefixed(&formatted), Some(*answer));
+        assert_eq!(parse_prefixed(&formatted[7..]), Some(*answer));
+        assert_eq!(parse_prefixed(&formatted.to_uppercase()[7..]),
+                   Some(*answer));
+        for bad_prefix in &["sha512:", "SHA256:", "sha256", ":", " "] {
+            let bad = format!("{}{}", bad_prefix, &formatted[7..]);
+            assert_eq!(parse_prefixed(&bad), None);
+        }
+        assert_eq!(parse_prefixed(&formatted[..formatted.len()-1]), None);
+        assert_eq!(parse_prefixed(&format!("{}0", formatted)), None);
+    }
+    assert_eq!(parse_prefixed(&format!("sha256:{}", "g".repeat(64))), None);
+}
+#[test]
+pub fn hash_matrix_shapes() {
+    let data: Vec<u8> = (0 .. 24).collect();
+    let shapes = [(1, 24), (2, 12), (3, 8), (4, 6), (6, 4), (2, 6), (24, 1)];
+    for (i, a) in shapes.iter().enumerate() {
+        for b in &shapes[i+1 ..] {
+            assert_ne!(hash_matrix(a.0, a.1, &data),
+                       hash_matrix(b.0, b.1, &data));
+        }
+    }
+    let mut prefixed = Vec::new();
+    prefixed.extend_from_slice(&2u64.to_be_bytes());
+    prefixed.extend_from_slice(&12u64.to_be_bytes());
+    prefixed.extend_from_slice(&data);
+    assert_eq!(hash_matrix(2, 12, &data), hash(&prefixed));
+}
+#[test]
+#[cfg(feature="std")]
+pub fn verify_file_matches() {
+    let path = std::env::temp_dir()
+        .join(format!("lsx-verify-file-{}", std::process::id()));
+    let data: Vec<u8> = (0 .. 20000u32).map(|x| (x * 7) as u8).collect();
+    std::fs::write(&path, &data).unwrap();
+    let mut expected = hash(&data);
+    let matches = verify_file(&path, &expected);
+    expected[31] ^= 1;
+    let mismatches = verify_file(&path, &expected);
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches.unwrap());
+    assert!(!mismatches.unwrap());
+    assert!(verify_file(&path, &expected).is_err());
+}
+#[test]
+pub fn update_zeros() {
+    let prefix_data: Vec<u8> = (1 ..= 100).collect();
+    for prefix in &[0, 1, 63, 64, 100] {
+        for count in &[0, 1, 13, 63, 64, 65, 128, 1000, 4097] {
+            let data = vec![0u8; *count];
+            let mut sparse = BufSha256::new();
+            sparse.update(&prefix_data[.. *prefix]);
+            let mut dense = BufSha256::new();
+            dense.update(&prefix_data[.. *prefix]);
+            sparse.update_zeros(*count as u64);
+            dense.update(&data);
+            sparse.update(b"trailer");
+            dense.update(b"trailer");
+            assert_eq!(sparse.finish(&[]), dense.finish(&[]));
+        }
+    }
+}
+#[test]
+pub fn state_words() {
+    let data = KNOWN_ANSWERS.last().unwrap();
+    let mut hasher = RawSha256::new();
+    assert_eq!(hasher.state_words()[0], 0x6a09e667);
+    hasher.update(&data.0[.. BLOCKBYTES * 2]);
+    let resumed = RawSha256::from_state(hasher.state_words(),
+                                        (BLOCKBYTES * 2) as u64);
+    assert_eq!(resumed.finish(&data.0[BLOCKBYTES * 2 ..]), data.1);
+}
+#[test]
+pub fn export_import_state() {
+    let data = KNOWN_ANSWERS.last().unwrap();
+    let half = data.0.len() / 2 / BLOCKBYTES * BLOCKBYTES;
+    let mut hasher = RawSha256::new();
+    hasher.update(&data.0[.. half]);
+    let exported = hasher.export_state();
+    assert_eq!(&exported[32..], &(half as u64).to_be_bytes());
+    let resumed = RawSha256::import_state(&exported);
+    assert_eq!(resumed.export_state(), exported);
+    assert_eq!(resumed.finish(&data.0[half ..]), data.1);
+}
+#[test]
+pub fn hash_list_framing() {
+    assert_ne!(hash_list(&[b"ab", b"c"]), hash_list(&[b"a", b"bc"]));
+    assert_ne!(hash_list(&[b"abc"]), hash_list(&[b"abc", b""]));
+    assert_ne!(hash_list(&[]), hash_list(&[b""]));
+    assert_eq!(hash_list(&[b"ab", b"c", b"", b"The quick brown fox"]),
+               [0xad,0x15,0x47,0xad,0x9d,0x97,0x1c,0xe5,0x29,0x2e,0xed,0xc8,
+                0x2f,0xda,0xea,0xa5,0xbb,0x06,0x43,0x4d,0xb7,0xed,0x2e,0x9c,
+                0x48,0x3a,0x15,0xcc,0x95,0x5a,0x34,0x9f]);
+}
+#[test]
+pub fn verifier() {
+    for (data, answer) in KNOWN_ANSWERS {
+        let mut verifier = Verifier::new(answer);
+        for chunk in data.chunks(7) { verifier.update(chunk) }
+        assert!(verifier.verify());
+        for i in 0 .. HASHBYTES {
+            let mut wrong_answer = *answer;
+            wrong_answer[i] ^= 0x80;
+            let mut verifier = Verifier::new(&wrong_answer);
+            verifier.update(data);
+            assert!(!verifier.verify());
+        }
+        if !data.is_empty() {
+            let mut verifier = Verifier::new(answer);
+            verifier.update(&data[1..]);
+            assert!(!verifier.verify());
+        }
+    }
+}
+#[test]
+#[cfg(feature="debug-capture")]
+pub fn capture() {
+    for (data, answer) in KNOWN_ANSWERS {
+        let mut hasher = CapturingSha256::<100>::new();
+        for chunk in data.chunks(13) { hasher.update(chunk) }
+        assert_eq!(hasher.captured(), &data[.. data.len().min(100)]);
+        assert_eq!(hasher.finish(&[]), *answer);
+    }
+}
+#[test]
+pub fn hash_iterated_chain() {
+    assert_eq!(hash_iterated(b"password", 0), hash(b"password"));
+    assert_eq!(hash_iterated(b"password", 1), hash(b"password"));
+    assert_eq!(hash_iterated(b"password", 2), hash(&hash(b"password")));
+    assert_eq!(hash_iterated(b"password", 3),
+               [0xc0,0xa8,0x85,0x34,0xe7,0x2a,0xc9,0x1a,0xea,0x54,0x52,0x2c,
+                0xb4,0xaa,0xef,0xd9,0x28,0x62,0xe6,0x8e,0x05,0x75,0x1f,0x7f,
+                0x68,0x48,0x89,0xd9,0xd1,0xc4,0xa6,0x26]);
+}
+#[test]
+pub fn finalize_into_reset() {
+    let mut hasher = BufSha256::new();
+    let mut out = [0u8; HASHBYTES];
+    for (data, answer) in KNOWN_ANSWERS {
+        hasher.update(data);
+        hasher.finalize_into_reset(&mut out);
+        assert_eq!(out, *answer);
+        assert_eq!(hasher.buffered_bytes, 0);
+        assert_eq!(hasher.buf, [0; BLOCKBYTES]);
+    }
+}
+#[test]
+#[cfg(feature="std")]
+pub fn hash_reader_and_len() {
+    for (data, answer) in KNOWN_ANSWERS {
+        assert_eq!(hash_and_len(data), (*answer, data.len()));
+        // read it in dribs and drabs, to make sure partial reads are handled
+        use std::io::Read;
+        let mut reader = (&data[..]).take(1)
+            .chain(&data[data.len().min(1) ..]);
+        assert_eq!(super::hash_reader_and_len(&mut reader).unwrap(),
+                   (*answer, data.len() as u64));
+    }
+    let big = vec![0x5Au8; 100000];
+    assert_eq!(super::hash_reader_and_len(&mut &big[..]).unwrap(),
+               (hash(&big), big.len() as u64));
+}
+#[test]
+pub fn hash_into_sizes() {
+    let data = KNOWN_ANSWERS.last().unwrap();
+    for len in 0 .. HASHBYTES {
+        let mut out = [0xEEu8; HASHBYTES];
+        assert_eq!(hash_into(data.0, &mut out[.. len]), Err(HASHBYTES));
+        assert_eq!(out, [0xEE; HASHBYTES]);
+    }
+    let mut out = [0xEEu8; HASHBYTES + 1];
+    assert_eq!(hash_into(data.0, &mut out[..]), Ok(()));
+    assert_eq!(out[.. HASHBYTES], data.1);
+    assert_eq!(out[HASHBYTES], 0xEE);
+}
+#[test]
+pub fn merkle_root_shapes() {
+    let leaves: Vec<[u8; HASHBYTES]>
+        = (0u8 .. 5).map(|x| hash(&[x])).collect();
+    assert_eq!(merkle_root(&[]), hash(b""));
+    assert_eq!(merkle_root(&leaves[.. 1]), leaves[0]);
+    let ab = merkle_node(&leaves[0], &leaves[1]);
+    let cd = merkle_node(&leaves[2], &leaves[3]);
+    let abcd = merkle_node(&ab, &cd);
+    assert_eq!(merkle_root(&leaves[.. 2]), ab);
+    assert_eq!(merkle_root(&leaves[.. 3]),
+               merkle_node(&ab, &leaves[2]));
+    assert_eq!(merkle_root(&leaves[.. 4]), abcd);
+    assert_eq!(merkle_root(&leaves[.. 5]),
+               merkle_node(&abcd, &leaves[4]));
+}
+#[test]
+pub fn finish_partial_matches_finish() {
+    let mut block = [0u8; BLOCKBYTES];
+    for (n, x) in block.iter_mut().enumerate() { *x = (n * 7) as u8 }
+    let start = || RawSha256::new().chain(&[0x5A; BLOCKBYTES * 2]);
+    for len in 0 ..= BLOCKBYTES {
+        assert_eq!(start().finish_partial(&block, len),
+                   start().finish(&block[.. len]));
+    }
+}
+#[test]
+#[should_panic]
+pub fn finish_partial_too_long() {
+    let _ = RawSha256::new().finish_partial(&[0; BLOCKBYTES],
+                                            BLOCKBYTES + 1);
+}
+#[test]
+pub fn update_vectored_matches_concatenation() {
+    let data: Vec<u8> = (0 .. 500u32).map(|x| (x * 17) as u8).collect();
+    let expected = hash(&data);
+    for &(a, b, c) in &[(0, 0, 0), (1, 63, 64), (64, 64, 64), (10, 200, 3),
+                        (500, 0, 0), (0, 0, 500), (70, 0, 130)] {
+        let (x, rest) = data.split_at(a);
+        let (y, rest) = rest.split_at(b);
+        let (z, rest) = rest.split_at(c);
+        let mut hasher = BufSha256::new();
+        hasher.update_vectored(&[x, y, z, rest]);
+        assert_eq!(hasher.byte_count(), data.len() as u64);
+        assert_eq!(hasher.finish(&[]), expected);
+    }
+}
+#[test]
+pub fn into_raw_when_aligned() {
+    let data: Vec<u8> = (0 .. 300u32).map(|x| (x * 5) as u8).collect();
+    let mut hasher = BufSha256::new();
+    hasher.update(&data[.. 100]);
+    let mut hasher = hasher.into_raw().unwrap_err();
+    assert_eq!(hasher.byte_count(), 100);
+    hasher.update(&data[100 .. 128]);
+    let mut raw = hasher.into_raw().unwrap();
+    assert_eq!(raw.byte_count(), 128);
+    raw.update(&data[128 .. 256]);
+    assert_eq!(raw.finish(&data[256 ..]), hash(&data));
+    assert!(BufSha256::new().into_raw().is_ok());
+}
+#[test]
+pub fn compress_with_manual_padding() {
+    for &len in &[0usize, 3, 55, 56, 64, 100, 119, 120, 200] {
+        let data: Vec<u8> = (0 .. len).map(|n| (n * 29) as u8).collect();
+        // the standard padding: 0x80, zeroes up to 56 mod 64, then the
+        // length in bits
+        let mut padded = data.clone();
+        padded.push(0x80);
+        while padded.len() % BLOCKBYTES != BLOCKBYTES - 8 { padded.push(0) }
+        padded.extend_from_slice(&(len as u64 * 8).to_be_bytes());
+        let mut hasher = RawSha256::new();
+        for block in padded.chunks_exact(BLOCKBYTES) {
+            hasher.compress(array_ref!(block, 0, BLOCKBYTES));
+        }
+        assert_eq!(hasher.byte_count(), 0);
+        let mut digest = [0; HASHBYTES];
+        for (out, word) in digest.chunks_exact_mut(4)
+            .zip(hasher.state_words().iter()) {
+            out.copy_from_slice(&word.to_be_bytes());
+        }
+        assert_eq!(digest, hash(&data));
+    }
+}
+#[test]
+#[cfg(feature="alloc")]
+pub fn tree_hasher_chunks() {
+    let data: Vec<u8> = (0 .. 1000u32).map(|x| (x * 13) as u8).collect();
+    for &chunk_size in &[1, 64, 100, 333, 1000, 5000] {
+        let expected_leaves: Vec<[u8; HASHBYTES]>
+            = data.chunks(chunk_size).map(hash).collect();
+        let expected_root = hash(&expected_leaves.concat());
+        // feed it in awkwardly-sized pieces, to cross chunk boundaries
+        let mut hasher = TreeHasher::new(chunk_size);
+        let mut leaves = Vec::new();
+        for piece in data.chunks(37) {
+            hasher.update_with(piece, |i, leaf| {
+                assert_eq!(i, leaves.len() as u64);
+                leaves.push(*leaf);
+            });
+        }
+        let root = hasher.finish_with(|_, leaf| leaves.push(*leaf));
+        assert_eq!(leaves, expected_leaves);
+        assert_eq!(root, expected_root);
+    }
+    assert_eq!(TreeHasher::new(10).finish(), hash(&hash(b"")));
+}
+#[test]
+#[cfg(feature="alloc")]
+pub fn hash_records_root() {
+    let data: Vec<u8> = (0 .. 7 * 100).map(|x| x as u8).collect();
+    let (hashes, root) = hash_records(100, &data);
+    assert_eq!(hashes.len(), 7);
+    for (record, hash_) in data.chunks(100).zip(hashes.iter()) {
+        assert_eq!(hash(record), *hash_);
+    }
+    assert_eq!(root, merkle_root(&hashes));
+    let (hashes, root) = hash_records(5, &[]);
+    assert!(hashes.is_empty());
+    assert_eq!(root, merkle_root(&[]));
+}
+#[test]
+#[should_panic]
+#[cfg(feature="alloc")]
+pub fn hash_records_partial() {
+    let _ = hash_records(3, b"four");
+}
+#[test]
+#[cfg(feature="rayon")]
+pub fn hash_many_matches_serial() {
+    let data: Vec<u8> = (0 .. 5000u32).map(|x| (x * 7) as u8).collect();
+    let inputs: Vec<&[u8]> = (0 .. 100).map(|n| &data[n .. n * 50]).collect();
+    let serial: Vec<[u8; HASHBYTES]>
+        = inputs.iter().map(|x| hash(x)).collect();
+    assert_eq!(hash_many(&inputs), serial);
+    assert!(hash_many(&[]).is_empty());
+}
+#[test]
+pub fn round_words_matches_bytes() {
+    let data: Vec<u8> = (0 .. BLOCKBYTES * 3).map(|n| (n * 13) as u8)
+        .collect();
+    let mut by_words = RawSha256::new();
+    for block in data.chunks_exact(BLOCKBYTES) {
+        let mut words = [0u32; 16];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_be_bytes(*array_ref!(bytes, 0, 4));
+        }
+        by_words.round_words(&words);
+    }
+    let mut by_bytes = RawSha256::new();
+    by_bytes.update(&data);
+    assert_eq!(by_words.byte_count(), by_bytes.byte_count());
+    assert_eq!(by_words.finish(b"tail"), by_bytes.finish(b"tail"));
+    // the byte order of the words doesn't depend on the target's
+    let mut words = [0u32; 16];
+    words[0] = 0x61626380;
+    words[15] = 24;
+    assert_eq!(RawSha256::new().round_words(&words).export_state()[.. 32],
+               hash(b"abc")[..]);
+}
+#[test]
+pub fn associated_sizes() {
+    assert_eq!(RawSha256::OUTPUT_LEN, RawSha256::new().finish(&[]).len());
+    assert_eq!(BufSha256::OUTPUT_LEN, BufSha256::new().finish(&[]).len());
+    assert_eq!(RawSha224::OUTPUT_LEN, RawSha224::new().finish(&[]).len());
+    assert_eq!(BufSha224::OUTPUT_LEN, BufSha224::new().finish(&[]).len());
+    assert_eq!(RawSha256::BLOCK_LEN, BLOCKBYTES);
+    assert_eq!(BufSha256::BLOCK_LEN, BLOCKBYTES);
+    assert_eq!(RawSha224::BLOCK_LEN, BLOCKBYTES);
+    assert_eq!(BufSha224::BLOCK_LEN, BLOCKBYTES);
+}
+/// Sets the process-wide backend for the length of one test. Tests that use
+/// it run one at a time, and the old backend is put back even if the test
+/// panics.
+struct BackendGuard {
+    previous: Backend,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+impl BackendGuard {
+    fn set(backend: Backend) -> BackendGuard {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        let lock = LOCK.lock().unwrap_or_else(|poison| poison.into_inner());
+        let previous = super::backend();
+        set_backend(backend);
+        BackendGuard { previous, _lock: lock }
+    }
+}
+impl Drop for BackendGuard {
+    fn drop(&mut self) {
+        set_backend(self.previous);
+    }
+}
+#[test]
+pub fn forced_scalar_backend() {
+    let auto: Vec<[u8; HASHBYTES]>
+        = KNOWN_ANSWERS.iter().map(|(data, _)| hash(data)).collect();
+    let _guard = BackendGuard::set(Backend::Scalar);
+    assert_eq!(backend(), Backend::Scalar);
+    for ((data, expected), auto) in KNOWN_ANSWERS.iter().zip(auto.iter()) {
+        assert_eq!(hash(data), *auto);
+        assert_eq!(hash(data), *expected);
+    }
+}
+#[test]
+pub fn pad_then_finalize() {
+    for (data, answer) in KNOWN_ANSWERS {
+        let mut hasher = BufSha256::new();
+        hasher.update(data);
+        let (padding, len) = hasher.pad();
+        let remainder = data.len() % BLOCKBYTES;
+        assert_eq!(len, if remainder > 55 { 128 } else { 64 });
+        assert_eq!(padding[.. remainder], data[data.len() - remainder ..]);
+        assert_eq!(padding[remainder], 0x80);
+        assert_eq!(hasher.finalize_padded(&padding[.. len]), *answer);
+    }
+}
+#[test]
+pub fn tagged_hash_and_commit_key() {
+    // BIP 340's challenge tag, checked against an independent implementation
+    assert_eq!(tagged_hash(b"BIP0340/challenge", b"abc"),
+               [0x77,0x0a,0x5b,0x7e,0x7c,0x30,0x4b,0xbc,0xc3,0xea,0x10,0x73,
+                0x43,0xff,0x95,0x1d,0xd4,0x04,0x31,0x2e,0xf4,0x18,0xdb,0x0c,
+                0x3b,0x94,0xe2,0xeb,0xfb,0xb5,0x00,0x87]);
+    let tag = hash(b"lsx/commit-key");
+    let mut prefixed = tag.to_vec();
+    prefixed.extend_from_slice(&tag[..]);
+    prefixed.extend_from_slice(b"key");
+    assert_eq!(commit_key(b"key"), hash(&prefixed));
+    // this must never change, or old commitments won't verify
+    let key: Vec<u8> = (0 .. 16).collect();
+    assert_eq!(commit_key(&key),
+               [0xfc,0x56,0x55,0x74,0x0b,0x88,0xc1,0xaf,0x02,0xa7,0xb5,0xdb,
+                0xad,0xf5,0x40,0x0f,0x88,0xc0,0xa2,0xc4,0x16,0xd4,0x16,0x55,
+                0x4a,0xb6,0xfb,0x11,0x88,0x5b,0x63,0x95]);
+    assert_ne!(commit_key(&key[.. 15]), commit_key(&key));
+}
+#[test]
+#[cfg(feature="unicode")]
+pub fn hash_str_nfc_equivalents() {
+    let composed = "caf\u{E9} \u{212B}";
+    let decomposed = "cafe\u{301} A\u{30A}";
+    assert_ne!(composed, decomposed);
+    assert_eq!(hash_str_nfc(composed), hash_str_nfc(decomposed));
+    assert_eq!(hash_str_nfc(decomposed), hash("caf\u{E9} \u{C5}".as_bytes()));
+    assert_eq!(hash_str_nfc("plain ASCII"), hash(b"plain ASCII"));
+    assert_ne!(hash_str_nfc("\u{FB01}"), hash_str_nfc("fi"));
+}
+#[test]
+pub fn digest_diff_patterns() {
+    let a = hash(b"digest_diff");
+    assert_eq!(digest_diff(&a, &a), None);
+    let mut b = a;
+    b[0] ^= 0xFF;
+    assert_eq!(digest_diff(&a, &b), Some((0, 1)));
+    let mut b = a;
+    b[31] ^= 0x01;
+    assert_eq!(digest_diff(&a, &b), Some((31, 1)));
+    let mut b = a;
+    for x in b[10 .. 20].iter_mut() { *x = !*x }
+    assert_eq!(digest_diff(&a, &b), Some((10, 10)));
+    assert_eq!(digest_diff(&b, &a), Some((10, 10)));
+    let mut b = a;
+    for x in b.iter_mut() { *x = !*x }
+    assert_eq!(digest_diff(&a, &b), Some((0, HASHBYTES)));
+}
+#[test]
+pub fn merkle_builder_matches() {
+    let data: Vec<u8> = (0 .. 70).collect();
+    let leaves: Vec<[u8; HASHBYTES]>
+        = data.chunks(1).map(hash).collect();
+    for n in 0 .. leaves.len() {
+        let mut builder = MerkleBuilder::new();
+        for leaf in &data[.. n] {
+            builder.push_leaf(&[*leaf]);
+        }
+        assert_eq!(builder.leaf_count(), n as u64);
+        assert_eq!(builder.depth, (n as u64).count_ones() as usize);
+        assert_eq!(builder.finish(), merkle_root(&leaves[.. n]));
+    }
+}
+#[test]
+pub fn hash_u64_range_vectors() {
+    assert_eq!(hash_u64_range(0, 10),
+               [0x84,0x14,0x1a,0xab,0x90,0x6f,0xb1,0x95,0x4a,0xa2,0x01,0x90,
+                0x23,0x25,0x52,0x58,0xb2,0xe3,0x88,0xf4,0x67,0xc7,0xbc,0x47,
+                0x29,0x46,0x67,0xaa,0xa7,0x49,0xcd,0x00]);
+    assert_eq!(hash_u64_range(0, 1),
+               [0xaf,0x55,0x70,0xf5,0xa1,0x81,0x0b,0x7a,0xf7,0x8c,0xaf,0x4b,
+                0xc7,0x0a,0x66,0x0f,0x0d,0xf5,0x1e,0x42,0xba,0xf9,0x1d,0x4d,
+                0xe5,0xb2,0x32,0x8d,0xe0,0xe8,0x3d,0xfc]);
+    assert_eq!(hash_u64_range(7, 7), hash(b""));
+    assert_eq!(hash_u64_range(8, 7), hash(b""));
+    let bytes: Vec<u8>
+        = (1000u64 .. 1100).flat_map(|x| x.to_be_bytes()).collect();
+    assert_eq!(hash_u64_range(1000, 1100), hash(&bytes));
+}
+#[test]
+#[cfg(feature="bytes")]
+pub fn hash_buf_chained() {
+    use bytes::Buf;
+    for (data, answer) in KNOWN_ANSWERS {
+        for split in &[0, 1, 17, 64] {
+            let split = (*split).min(data.len());
+            let mut buf = (&data[.. split]).chain(&data[split ..]);
+            assert_eq!(hash_buf(&mut buf), *answer);
+            assert!(!buf.has_remaining());
+        }
+    }
+    let mut bytes = bytes::Bytes::from_static(b"abc");
+    assert_eq!(hash_buf(&mut bytes), hash(b"abc"));
+    assert!(bytes.is_empty());
+}
+#[test]
+pub fn embedded_checksum() {
+    let original: Vec<u8> = (0 .. 200u32).map(|x| (x * 3) as u8).collect();
+    for &offset in &[0, 1, 64, 100, 168] {
+        let mut data = original.clone();
+        hash_with_embedded_checksum(&mut data, offset);
+        let mut zeroed = original.clone();
+        for x in zeroed[offset .. offset + HASHBYTES].iter_mut() { *x = 0 }
+        assert_eq!(data[offset .. offset + HASHBYTES], hash(&zeroed));
+        assert!(verify_embedded_checksum(&data, offset));
+        assert!(!verify_embedded_checksum(&data, offset + 1));
+        for i in (0 .. data.len()).step_by(7) {
+            let mut tampered = data.clone();
+            tampered[i] ^= 0x10;
+            assert!(!verify_embedded_checksum(&tampered, offset));
+        }
+    }
+    assert!(!verify_embedded_checksum(&original, 169));
+    assert!(!verify_embedded_checksum(&original, usize::MAX));
+}
+#[test]
+#[should_panic]
+pub fn embedded_checksum_out_of_range() {
+    hash_with_embedded_checksum(&mut [0; 40], 9);
+}
+#[test]
+pub fn sha224() {
+    // Known answers from FIPS 180-4's examples (and the empty string)
+    const ANSWERS: &[(&[u8], [u8; HASHBYTES_224])] = &[
+        (b"", [0xd1,0x4a,0x02,0x8c,0x2a,0x3a,0x2b,0xc9,0x47,0x61,0x02,0xbb,0x28,0x82,0x34,0xc4,0x15,0xa2,0xb0,0x1f,0x82,0x8e,0xa6,0x2a,0xc5,0xb3,0xe4,0x2f]),
+        (b"abc", [0x23,0x09,0x7d,0x22,0x34,0x05,0xd8,0x22,0x86,0x42,0xa4,0x77,0xbd,0xa2,0x55,0xb3,0x2a,0xad,0xbc,0xe4,0xbd,0xa0,0xb3,0xf7,0xe3,0x6c,0x9d,0xa7]),
+        (b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq", [0x75,0x38,0x8b,0x16,0x51,0x27,0x76,0xcc,0x5d,0xba,0x5d,0xa1,0xfd,0x89,0x01,0x50,0xb0,0xc6,0x45,0x5c,0xb4,0xf5,0x8b,0x19,0x52,0x52,0x25,0x25]),
+    ];
+    for (data, answer) in ANSWERS {
+        assert_eq!(hash224(data), *answer);
+        let mut raw = RawSha224::new();
+        let split = data.len() - data.len() % BLOCKBYTES;
+        raw.update(&data[.. split]);
+        assert_eq!(raw.finish(&data[split ..]), *answer);
+        for chunksize in &[1, 7, 64] {
+            let mut hasher = BufSha224::new();
+            for chunk in data.chunks(*chunksize) { hasher.update(chunk) }
+            assert_eq!(hasher.finish(&[]), *answer);
+        }
+    }
+}
+#[test]
+#[cfg(feature="alloc")]
+pub fn hex_round_trip() {
+    assert_eq!(to_hex(&KNOWN_ANSWERS[2].1),
+               "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    for (_, answer) in KNOWN_ANSWERS {
+        let hex = to_hex(answer);
+        let mut hex_into = [0u8; HASHBYTES*2];
+        to_hex_into(answer, &mut hex_into);
+        assert_eq!(hex.as_bytes(), &hex_into[..]);
+        assert!(hex.bytes().all(|x| matches!(x, b'0' ..= b'9' | b'a' ..= b'f')));
+        assert_eq!(parse_hex(&hex), Some(*answer));
+    }
+}
+#[test]
+pub fn typed_hash() {
+    let (data, answer) = KNOWN_ANSWERS[0];
+    let typed = hash_typed(data);
+    assert_eq!(*typed, answer);
+    assert_eq!(typed, Sha256Hash::from(answer));
+    assert_ne!(typed, hash_typed(b"abd"));
+    assert_eq!(typed.into_bytes(), answer);
+    assert_eq!(<[u8; HASHBYTES]>::from(typed), answer);
+    assert_eq!(typed.as_ref(), &answer[..]);
+    let mut hasher = BufSha256::new();
+    hasher.update(data);
+    assert_eq!(hasher.finish_typed(&[]), typed);
+    let lower = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assert_eq!(format!("{}", typed), lower);
+    assert_eq!(format!("{:x}", typed), lower);
+    assert_eq!(format!("{:X}", typed), lower.to_uppercase());
+    assert_eq!(format!("{:?}", typed), format!("Sha256Hash({})", lower));
+}
+#[test]
+#[cfg(feature="alloc")]
+pub fn from_hex_errors() {
+    for (_, answer) in KNOWN_ANSWERS {
+        let hex = to_hex(answer);
+        assert_eq!(from_hex(&hex), Ok(*answer));
+        assert_eq!(from_hex(&hex.to_uppercase()), Ok(*answer));
+        assert_eq!(hex.parse::<Sha256Hash>(), Ok(Sha256Hash(*answer)));
+        assert_eq!(from_hex(&hex[.. 63]), Err(ParseError { position: 63 }));
+        assert_eq!(from_hex(&format!("{}0", hex)),
+                   Err(ParseError { position: 64 }));
+        for position in &[0, 1, 31, 63] {
+            let mut bad = hex.clone().into_bytes();
+            bad[*position] = b'g';
+            let bad = String::from_utf8(bad).unwrap();
+            assert_eq!(from_hex(&bad), Err(ParseError { position: *position }));
+            // a bad digit is reported even if the length is also wrong
+            assert_eq!(from_hex(&bad[.. 63.max(*position + 1)]).unwrap_err()
+                       .position(), *position);
+        }
+    }
+    assert_eq!(from_hex(""), Err(ParseError { position: 0 }));
+    assert_eq!(from_hex("sha256:"), Err(ParseError { position: 0 }));
+    assert_eq!(from_hex("\u{e9}").unwrap_err().position(), 0);
+}
+#[test]
+pub fn verify_eq_results() {
+    for (_, answer) in KNOWN_ANSWERS {
+        assert!(verify_eq(answer, answer));
+        for i in 0 .. HASHBYTES {
+            for bit in 0 .. 8 {
+                let mut wrong = *answer;
+                wrong[i] ^= 1 << bit;
+                assert!(!verify_eq(answer, &wrong));
+                assert!(!verify_eq(&wrong, answer));
+            }
+        }
+    }
+    assert!(verify_eq(&[0; HASHBYTES], &[0; HASHBYTES]));
+    assert!(!verify_eq(&[0; HASHBYTES], &[0xFF; HASHBYTES]));
+}
+#[test]
+pub fn verify_results() {
+    for (question, answer) in KNOWN_ANSWERS {
+        assert!(verify(question, answer));
+        let mut wrong = *answer;
+        wrong[HASHBYTES - 1] ^= 1;
+        assert!(!verify(question, &wrong));
+    }
+    assert!(!verify(b"abd", &hash(b"abc")));
+}
+#[test]
+#[cfg(feature="zeroize")]
+pub fn zeroize() {
+    use zeroize::Zeroize;
+    fn check<T: Copy + Zeroize>() {}
+    check::<RawSha256>();
+    check::<BufSha256>();
+    check::<RawSha224>();
+    check::<BufSha224>();
+    check::<Verifier>();
+    let mut hasher = BufSha256::new();
+    hasher.update(b"secret");
+    hasher.zeroize();
+    assert_eq!(hasher.inner.h, [0; 8]);
+    assert_eq!(hasher.inner.byte_count, 0);
+    assert_eq!(hasher.buf, [0; BLOCKBYTES]);
+    assert_eq!(hasher.buffered_bytes, 0);
+}
+#[test]
+#[cfg(feature="serde")]
+pub fn serde_round_trip() {
+    let data = KNOWN_ANSWERS.last().unwrap();
+    let mut hasher = BufSha256::new();
+    hasher.update(&data.0[.. 100]);
+    let json = serde_json::to_string(&hasher).unwrap();
+    let resumed: BufSha256 = serde_json::from_str(&json).unwrap();
+    assert_eq!(resumed.finish(&data.0[100 ..]), data.1);
+    let mut raw = RawSha256::new();
+    raw.update(&data.0[.. 128]);
+    let json = serde_json::to_string(&raw).unwrap();
+    let resumed: RawSha256 = serde_json::from_str(&json).unwrap();
+    assert_eq!(resumed.finish(&data.0[128 ..]), data.1);
+}
+#[test]
+#[cfg(feature="serde")]
+pub fn serde_rejects_bad_state() {
+    let h = "[1,2,3,4,5,6,7,8]";
+    let buffered = |n: usize| format!("[{}]", vec!["0"; n].join(","));
+    let buf = |count: u64, n: usize| serde_json::from_str::<BufSha256>(
+        &format!(r#"{{"h":{},"byte_count":{},"buffered":{}}}"#,
+                 h, count, buffered(n)));
+    assert!(buf(64, 0).is_ok());
+    assert!(buf(64, 63).is_ok());
+    assert!(buf(64, 64).is_err());
+    assert!(buf(65, 0).is_err());
+    let raw = |count: u64| serde_json::from_str::<RawSha256>(
+        &format!(r#"{{"h":{},"byte_count":{}}}"#, h, count));
+    assert!(raw(128).is_ok());
+    assert!(raw(100).is_err());
+}
+#[test]
+pub fn try_update_lengths() {
+    let data = KNOWN_ANSWERS.last().unwrap();
+    let mut hasher = RawSha256::new();
+    for &bad in &[1, 63, 65, 100] {
+        assert_eq!(hasher.try_update(&data.0[.. bad]),
+                   Err(BlockSizeError { len: bad }));
+    }
+    assert_eq!(hasher.try_update(&[]), Ok(()));
+    assert_eq!(hasher.try_update(&data.0[.. BLOCKBYTES * 2]), Ok(()));
+    assert_eq!(hasher.finish(&data.0[BLOCKBYTES * 2 ..]), data.1);
+}
+#[test]
+pub fn finish_into_matches() {
+    for (data, answer) in KNOWN_ANSWERS {
+        let mut out = [0u8; HASHBYTES];
+        RawSha256::new().finish_into(data, &mut out);
+        assert_eq!(out, *answer);
+        let mut out = [0u8; HASHBYTES];
+        let mut hasher = BufSha256::new();
+        hasher.update(&data[.. data.len() / 2]);
+        hasher.finish_into(&data[data.len() / 2 ..], &mut out);
+        assert_eq!(out, *answer);
+    }
+}
+#[test]
+pub fn chained_updates() {
+    let data = KNOWN_ANSWERS.last().unwrap();
+    let (a, b) = data.0.split_at(BLOCKBYTES);
+    let (b, c) = b.split_at(BLOCKBYTES * 3);
+    let mut hasher = RawSha256::new();
+    hasher.update(a).update(b);
+    assert_eq!(hasher.finish(c), data.1);
+    assert_eq!(RawSha256::new().chain(a).chain(b).finish(c), data.1);
+    let mut hasher = BufSha256::new();
+    hasher.update(&a[..5]).update(&a[5..]).update(b);
+    assert_eq!(hasher.finish(c), data.1);
+    assert_eq!(BufSha256::new().chain(c).finish(&[]), hash(c));
+}
+#[test]
+pub fn byte_counts() {
+    let data = KNOWN_ANSWERS.last().unwrap().0;
+    let mut raw = RawSha256::new();
+    assert_eq!(raw.byte_count(), 0);
+    raw.update(&data[.. BLOCKBYTES]).update(&data[.. BLOCKBYTES * 3]);
+    assert_eq!(raw.byte_count(), (BLOCKBYTES * 4) as u64);
+    let mut hasher = BufSha256::new();
+    let mut total = 0;
+    for chunksize in &[1, 3, 60, 64, 65, 200] {
+        hasher.update(&data[.. *chunksize]);
+        total += *chunksize as u64;
+        assert_eq!(hasher.byte_count(), total);
+    }
+    hasher.update_zeros(1000);
+    assert_eq!(hasher.byte_count(), total + 1000);
+}
+#[test]
+pub fn peek_finish_interleaved() {
+    let data = KNOWN_ANSWERS.last().unwrap().0;
+    let mut raw = RawSha256::new();
+    let mut hasher = BufSha256::new();
+    let mut pos = 0;
+    for chunksize in &[1, 13, 64, 50, 128] {
+        hasher.update(&data[pos .. pos + chunksize]);
+        pos += chunksize;
+        assert_eq!(hasher.peek_finish(&[]), hash(&data[.. pos]));
+        assert_eq!(hasher.peek_finish(b"x"),
+                   BufSha256::new().chain(&data[.. pos]).finish(b"x"));
+        let whole = pos / BLOCKBYTES * BLOCKBYTES;
+        if whole > raw.byte_count() as usize {
+            raw.update(&data[raw.byte_count() as usize .. whole]);
+        }
+        assert_eq!(raw.peek_finish(&data[whole .. pos]), hash(&data[.. pos]));
+    }
+    assert_eq!(hasher.finish(&data[pos ..]), hash(data));
+}
+#[test]
+pub fn defaults() {
+    assert_eq!(RawSha256::default().state_words(),
+               RawSha256::new().state_words());
+    assert_eq!(RawSha256::default().byte_count(), 0);
+    assert_eq!(BufSha256::default().finish(b"abc"), hash(b"abc"));
+    assert_eq!(RawSha224::default().finish(b"abc"), hash224(b"abc"));
+    assert_eq!(BufSha224::default().finish(b"abc"), hash224(b"abc"));
+}
+#[test]
+#[cfg(feature="simd")]
+pub fn simd_schedule_matches() {
+    for seed in 0 .. 50u32 {
+        let mut block = [0u8; BLOCKBYTES];
+        for (n, x) in block.iter_mut().enumerate() {
+            *x = (seed.wrapping_mul(0x9E3779B9) >> (n % 25)) as u8 ^ n as u8;
+        }
+        assert_eq!(simd::schedule(&block)[..], schedule_scalar(&block)[..]);
+    }
+}
+#[test]
+#[cfg(feature="sha-ni")]
+pub fn sha_ni_matches() {
+    let data: Vec<u8> = (0 .. BLOCKBYTES * 20)
+        .map(|n| (n as u32).wrapping_mul(0x9E3779B9).rotate_left(7) as u8)
+        .collect();
+    for blocks in 0 ..= 20 {
+        let data = &data[.. blocks * BLOCKBYTES];
+        let mut portable = RawSha256::new();
+        for chunk in data.chunks_exact(BLOCKBYTES) {
+            portable.round(array_ref!(chunk, 0, BLOCKBYTES));
+        }
+        let mut h = RawSha256::new().h;
+        if !shani::compress(&mut h, data) {
+            eprintln!("SHA extensions unavailable, not testing them");
+            return
+        }
+        assert_eq!(h, portable.h);
+    }
+}
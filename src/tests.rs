@@ -0,0 +1,28 @@
+use super::*;
+
+/// Parse a string of hex digits, for tests that carry their vectors in hex.
+#[cfg(any(feature="sha256", feature="twofish"))]
+pub fn unhex(s: &str) -> Vec<u8> {
+    (0 .. s.len()).step_by(2)
+        .map(|n| u8::from_str_radix(&s[n .. n + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+pub fn features_match_cfg() {
+    let features = features();
+    assert_eq!(features.contains(&"std"), cfg!(feature="std"));
+    assert_eq!(features.contains(&"alloc"), cfg!(feature="alloc"));
+    assert_eq!(features.contains(&"sha256"), cfg!(feature="sha256"));
+    assert_eq!(features.contains(&"twofish"), cfg!(feature="twofish"));
+    assert_eq!(features.contains(&"ct-qbox"), cfg!(feature="ct-qbox"));
+    assert!(!features.contains(&"slow-tests"));
+}
+
+#[test]
+#[cfg(all(feature="std", feature="sha256", feature="twofish"))]
+pub fn default_features() {
+    for feature in ["std", "alloc", "sha256", "twofish"].iter() {
+        assert!(features().contains(feature));
+    }
+}
@@ -1,16 +1,120 @@
-//! An implementation of the [Twofish][1] block cipher. Only the primitive
-//! operation is supported; you will have to provide the rest (e.g. a
-//! particular CTR or CBC scheme) yourself. Enabled by default, or if you
-//! request the `"twofish"` feature.
+//! An implementation of the [Twofish][1] block cipher. Besides the primitive
+//! operation, a few common modes of operation are provided (e.g.
+//! [`Ctr`](struct.Ctr.html)). Enabled by default, or if you request the
+//! `"twofish"` feature.
 //!
 //! [1]: https://en.wikipedia.org/wiki/Twofish
 
+// (with "ct-qbox", the key schedule doesn't use most of the tables)
+#[cfg_attr(feature="ct-qbox", allow(dead_code))]
 mod tables;
 use tables::*;
+#[cfg(any(feature="ct-qbox", test))]
+mod ctqbox;
+#[cfg(feature="ct-qbox")]
+use ctqbox::{q0, q1, mdsq, rs_mul_column};
+
+pub mod cbc;
+pub use cbc::{CbcEncryptor, CbcDecryptor, unpad_pkcs7, PaddingError};
+#[cfg(feature="alloc")]
+pub use cbc::{pad_pkcs7, cbc_decrypt_unpad, CbcError};
+pub mod ccm;
+pub use ccm::Ccm;
+pub mod cfb;
+pub use cfb::Cfb;
+#[cfg(feature="cipher")]
+mod cipher_impl;
+pub mod cmac;
+pub use cmac::{Cmac, mac_only, verify_only};
+pub mod ctr;
+pub use ctr::{Ctr, CtrRng};
+#[cfg(feature="sha256")]
+pub use ctr::{hash_then_encrypt, EncryptAndHash};
+#[cfg(feature="std")]
+pub use ctr::{CtrWriter, CtrReader};
+pub mod eax;
+pub use eax::Eax;
+pub mod gcm;
+pub use gcm::TwofishGcm;
+pub mod ofb;
+pub use ofb::{Ofb, ofb_stream};
+pub mod xts;
+pub use xts::Xts;
 
 /// The number of bytes in a Twofish block.
 pub const BLOCKBYTES: usize = 16;
 
+/// Generate a fresh, random IV (or nonce) using the operating system's
+/// cryptographically secure random number generator. Every bit of the IV
+/// comes from the OS, so you can use it as-is with any of the block modes.
+/// Requires the `"getrandom"` feature.
+///
+/// Panics if the OS random number generator is unavailable. (If that happens,
+/// you have bigger problems than an unencrypted message.)
+#[cfg(feature="getrandom")]
+pub fn random_iv() -> [u8; BLOCKBYTES] {
+    let mut ret = [0u8; BLOCKBYTES];
+    getrandom::getrandom(&mut ret[..])
+        .expect("OS random number generator is unavailable");
+    ret
+}
+
+/// What [`Twofish::key_quality`] thinks of a key.
+#[derive(Copy,Clone,Debug,PartialEq,Eq)]
+#[non_exhaustive]
+pub enum KeyQuality {
+    /// Nothing obviously wrong with the key. (That doesn't mean it's good!)
+    Ok,
+    /// Every byte of the key is zero.
+    AllZero,
+    /// Every byte of the key is the same (nonzero) value.
+    AllSame,
+    /// Each byte of the key is one more (or one less) than the one before,
+    /// like `00 01 02 03 ...`.
+    Sequential,
+}
+
+/// The error returned by [`Twofish::new`] when the key isn't 16, 24, or 32
+/// bytes long.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct KeyLengthError {
+    len: usize,
+}
+
+impl KeyLengthError {
+    /// The length of the offending key, in bytes.
+    pub fn length(&self) -> usize {
+        self.len
+    }
+}
+
+impl core::fmt::Display for KeyLengthError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Twofish keys must be 16, 24, or 32 bytes long, not {}",
+               self.len)
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for KeyLengthError {}
+
+/// The error returned when an authenticated mode (e.g.
+/// [`TwofishGcm`](struct.TwofishGcm.html)) finds that a message's tag doesn't
+/// match. It doesn't say anything else; the ciphertext, the associated data,
+/// the nonce, the tag, or the key could be wrong, and there's no telling
+/// which.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct AuthenticationError;
+
+impl core::fmt::Display for AuthenticationError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "message failed authentication")
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for AuthenticationError {}
+
 /// The key-dependent data for a given Twofish key. This can be used to encrypt
 /// or decrypt blocks of data with that key. If you're inexperienced with
 /// crypto, you probably just thought of using this to encrypt/decrypt each
@@ -18,6 +122,17 @@ pub const BLOCKBYTES: usize = 16;
 /// [Wikipedia's article on block cipher modes of operation][1] so that you
 /// will understand that this is the least secure way to use a block cipher!
 ///
+/// A `Twofish` is never modified after it is constructed, and is both `Send`
+/// and `Sync`. It's perfectly fine to set up a single `Twofish` and share it
+/// (e.g. by reference or in an `Arc`) between many threads, each of which
+/// keeps its own mode state (counter, IV, etc.) and encrypts its own part of
+/// the data.
+///
+/// With the `"zeroize"` feature, `Twofish` implements `zeroize::Zeroize`,
+/// which overwrites the key schedule with zeroes. It is *not* wiped when
+/// dropped, since it's `Copy`: call `.zeroize()` yourself when you're done
+/// with it, or use a `ZeroizingTwofish`, which is wiped on drop.
+///
 /// [1]: https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation
 #[derive(Copy,Clone)]
 pub struct Twofish {
@@ -32,63 +147,73 @@ pub struct Twofish {
 fn splat(i: u8) -> [u8; 4] { [i, i, i, i] }
 fn unsplat(i: &[u32; 4]) -> u32 { i[0] ^ i[1] ^ i[2] ^ i[3] }
 
+#[cfg(not(feature="ct-qbox"))]
+#[inline(always)]
+fn q0(x: u8) -> u8 { Q0[x as usize] }
+#[cfg(not(feature="ct-qbox"))]
+#[inline(always)]
+fn q1(x: u8) -> u8 { Q1[x as usize] }
+#[cfg(not(feature="ct-qbox"))]
+#[inline(always)]
+fn mdsq(column: usize, x: u8) -> u32 { MDSQ[column][x as usize] }
+
 #[inline(always)]
 fn h_128(x: [u8; 4], key: &[u8; 12]) -> [u32; 4] {
     [
-        MDSQ[0][(Q0[(Q0[x[0] as usize] ^ key[8]) as usize] ^ key[0]) as usize],
-        MDSQ[1][(Q0[(Q1[x[1] as usize] ^ key[9]) as usize] ^ key[1]) as usize],
-        MDSQ[2][(Q1[(Q0[x[2] as usize] ^ key[10])as usize] ^ key[2]) as usize],
-        MDSQ[3][(Q1[(Q1[x[3] as usize] ^ key[11])as usize] ^ key[3]) as usize],
+        mdsq(0, q0(q0(x[0]) ^ key[8]) ^ key[0]),
+        mdsq(1, q0(q1(x[1]) ^ key[9]) ^ key[1]),
+        mdsq(2, q1(q0(x[2]) ^ key[10]) ^ key[2]),
+        mdsq(3, q1(q1(x[3]) ^ key[11]) ^ key[3]),
     ]
 }
 
 #[inline(always)]
 fn h_192(x: [u8; 4], key: &[u8; 20]) -> [u32; 4] {
     h_128([
-        Q1[x[0] as usize] ^ key[16],
-        Q1[x[1] as usize] ^ key[17],
-        Q0[x[2] as usize] ^ key[18],
-        Q0[x[3] as usize] ^ key[19],
+        q1(x[0]) ^ key[16],
+        q1(x[1]) ^ key[17],
+        q0(x[2]) ^ key[18],
+        q0(x[3]) ^ key[19],
     ], array_ref!(key, 0, 12))
 }
 
 #[inline(always)]
 fn h_256(x: [u8; 4], key: &[u8; 28]) -> [u32; 4] {
     h_192([
-        Q1[x[0] as usize] ^ key[24],
-        Q0[x[1] as usize] ^ key[25],
-        Q0[x[2] as usize] ^ key[26],
-        Q1[x[3] as usize] ^ key[27],
+        q1(x[0]) ^ key[24],
+        q0(x[1]) ^ key[25],
+        q0(x[2]) ^ key[26],
+        q1(x[3]) ^ key[27],
     ], array_ref!(key, 0, 20))
 }
 
 #[inline(always)]
 fn h_128_no_skip(x: [u8; 4], key: &[u8; 8]) -> [u32; 4] {
     [
-        MDSQ[0][(Q0[(Q0[x[0] as usize] ^ key[4]) as usize] ^ key[0]) as usize],
-        MDSQ[1][(Q0[(Q1[x[1] as usize] ^ key[5]) as usize] ^ key[1]) as usize],
-        MDSQ[2][(Q1[(Q0[x[2] as usize] ^ key[6]) as usize] ^ key[2]) as usize],
-        MDSQ[3][(Q1[(Q1[x[3] as usize] ^ key[7]) as usize] ^ key[3]) as usize],
+        mdsq(0, q0(q0(x[0]) ^ key[4]) ^ key[0]),
+        mdsq(1, q0(q1(x[1]) ^ key[5]) ^ key[1]),
+        mdsq(2, q1(q0(x[2]) ^ key[6]) ^ key[2]),
+        mdsq(3, q1(q1(x[3]) ^ key[7]) ^ key[3]),
     ]
 }
 
 #[inline(always)]
 fn h_192_no_skip(x: [u8; 4], key: &[u8; 12]) -> [u32; 4] {
     h_128_no_skip([
-        Q1[x[0] as usize] ^ key[8],
-        Q1[x[1] as usize] ^ key[9],
-        Q0[x[2] as usize] ^ key[10],
-        Q0[x[3] as usize] ^ key[11],
+        q1(x[0]) ^ key[8],
+        q1(x[1]) ^ key[9],
+        q0(x[2]) ^ key[10],
+        q0(x[3]) ^ key[11],
     ], array_ref!(key, 0, 8))
 }
 
 #[inline(always)]
 fn h_256_no_skip(x: [u8; 4], key: &[u8; 16]) -> [u32; 4] {
     h_192_no_skip([
-        Q1[x[0] as usize] ^ key[12],
-        Q0[x[1] as usize] ^ key[13],
-        Q0[x[2] as usize] ^ key[14],
-        Q1[x[3] as usize] ^ key[15],
+        q1(x[0]) ^ key[12],
+        q0(x[1]) ^ key[13],
+        q0(x[2]) ^ key[14],
+        q1(x[3]) ^ key[15],
     ], array_ref!(key, 0, 12))
 }
 
@@ -107,6 +232,7 @@ macro_rules! f {
     }
 }
 
+#[cfg(not(feature="ct-qbox"))]
 fn rs_mul_column(a: u8, b: u8, c: u8, d: u8, s: &mut [u8; 4], key_byte: u8) {
     if key_byte != 0 {
         let exp = RS_POLY_TO_EXP[(key_byte-1) as usize] as u32;
@@ -117,9 +243,11 @@ fn rs_mul_column(a: u8, b: u8, c: u8, d: u8, s: &mut [u8; 4], key_byte: u8) {
     }
 }
 
-macro_rules! define_twofish_new {
-    ($key_bits:expr, $hfunc:path, $hnoskipfunc:path, $key:expr) => {{
-        const KEY_BYTES: usize = $key_bits / 8;
+/// Build the key-dependent S-boxes. This is most of the work of the key
+/// schedule: 256 evaluations of the `h` function, against only 20 for the
+/// subkeys.
+macro_rules! define_sboxes {
+    ($key_bits:expr, $hnoskipfunc:path, $key:expr) => {{
         const KEY_CHUNKS: usize = $key_bits / 64;
         let mut s = [0u8; KEY_CHUNKS*4];
         for i in 0 .. KEY_CHUNKS {
@@ -138,6 +266,14 @@ macro_rules! define_twofish_new {
             sboxen[2][x as usize] = rows[2];
             sboxen[3][x as usize] = rows[3];
         }
+        sboxen
+    }}
+}
+
+macro_rules! define_twofish_new {
+    ($key_bits:expr, $hfunc:path, $hnoskipfunc:path, $key:expr) => {{
+        const KEY_BYTES: usize = $key_bits / 8;
+        let sboxen = define_sboxes!($key_bits, $hnoskipfunc, $key);
         let mut w = [0u32; 8];
         for i in 0..4 {
             let a = $hfunc(splat(2*i), array_ref!($key, 0, KEY_BYTES-4));
@@ -162,7 +298,52 @@ macro_rules! define_twofish_new {
     }}
 }
 
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for [u8; 16] {}
+    impl Sealed for [u8; 24] {}
+    impl Sealed for [u8; 32] {}
+}
+
+/// The key types [`Twofish::new_sized`] accepts: `[u8; 16]`, `[u8; 24]`, and
+/// `[u8; 32]`. This trait is sealed; there are no other Twofish key sizes to
+/// implement it for.
+pub trait KeyArray: sealed::Sealed {
+    /// Run the key schedule for this key.
+    fn key_schedule(&self) -> Twofish;
+    /// Build just the key-dependent S-boxes for this key. Requires the
+    /// `"bench"` feature.
+    #[cfg(feature="bench")]
+    fn sboxes(&self) -> [[u32; 256]; 4];
+}
+
+impl KeyArray for [u8; 16] {
+    fn key_schedule(&self) -> Twofish { Twofish::new128(self) }
+    #[cfg(feature="bench")]
+    fn sboxes(&self) -> [[u32; 256]; 4] {
+        define_sboxes!(128, h_128_no_skip, self)
+    }
+}
+
+impl KeyArray for [u8; 24] {
+    fn key_schedule(&self) -> Twofish { Twofish::new192(self) }
+    #[cfg(feature="bench")]
+    fn sboxes(&self) -> [[u32; 256]; 4] {
+        define_sboxes!(192, h_192_no_skip, self)
+    }
+}
+
+impl KeyArray for [u8; 32] {
+    fn key_schedule(&self) -> Twofish { Twofish::new256(self) }
+    #[cfg(feature="bench")]
+    fn sboxes(&self) -> [[u32; 256]; 4] {
+        define_sboxes!(256, h_256_no_skip, self)
+    }
+}
+
 impl Twofish {
+    /// The number of bytes in a Twofish block. The same as `BLOCKBYTES`.
+    pub const BLOCK_LEN: usize = BLOCKBYTES;
     /// Set up a context to en-/decrypt with a given 128-bit key.
     pub fn new128(key: &[u8; 16]) -> Twofish {
         define_twofish_new!(128, h_128, h_128_no_skip, key)
@@ -175,6 +356,107 @@ impl Twofish {
     pub fn new256(key: &[u8; 32]) -> Twofish {
         define_twofish_new!(256, h_256, h_256_no_skip, key)
     }
+    /// Set up a context to en-/decrypt with a key of any valid size: 16, 24,
+    /// or 32 bytes. The size is checked at compile time, and this compiles
+    /// down to a direct call to `new128`, `new192`, or `new256`.
+    ///
+    /// ```rust
+    /// # use lsx::twofish::Twofish;
+    /// let twofish = Twofish::new_sized(b"Twenty-four byte key!!!!");
+    /// ```
+    ///
+    /// Any other size is a compile error:
+    ///
+    /// ```rust,compile_fail
+    /// # use lsx::twofish::Twofish;
+    /// let twofish = Twofish::new_sized(b"Twenty byte key!!!!!");
+    /// ```
+    pub fn new_sized<const N: usize>(key: &[u8; N]) -> Twofish
+    where [u8; N]: KeyArray {
+        key.key_schedule()
+    }
+    /// Build only the key-dependent S-boxes for the given key, and nothing
+    /// else. This is for benchmarking the key schedule: building the S-boxes
+    /// takes 256 evaluations of Twofish's `h` function, against 20 for the
+    /// round subkeys, so it's most of the cost of rekeying. Requires the
+    /// `"bench"` feature, and isn't covered by semver; the S-box
+    /// representation may change.
+    #[cfg(feature="bench")]
+    pub fn build_sboxes<const N: usize>(key: &[u8; N]) -> [[u32; 256]; 4]
+    where [u8; N]: KeyArray {
+        key.sboxes()
+    }
+    /// Set up a context to en-/decrypt with a 256-bit key that has been split
+    /// into two shares, such that the key is `share_a XOR share_b`. The
+    /// combined key is only materialized in a local, which is scrubbed (on a
+    /// best-effort basis; see below) as soon as the key schedule is built.
+    ///
+    /// Safe Rust provides no way to guarantee that the compiler hasn't left
+    /// other copies of the key lying around in registers or on the stack. This
+    /// narrows the window; it doesn't close it.
+    pub fn new256_xor(share_a: &[u8; 32], share_b: &[u8; 32]) -> Twofish {
+        let mut key = [0u8; 32];
+        for ((k, a), b) in key.iter_mut().zip(share_a.iter())
+            .zip(share_b.iter()) {
+            *k = a ^ b;
+        }
+        let ret = Twofish::new256(&key);
+        key = [0u8; 32];
+        core::hint::black_box(&key);
+        ret
+    }
+    /// Set up a context from a key whose length is only known at runtime.
+    /// Returns an error if the key is not 16, 24, or 32 bytes long.
+    ///
+    /// ```rust
+    /// # use lsx::twofish::Twofish;
+    /// let key_material: &[u8] = b"Twenty-four byte key!!!!";
+    /// let twofish = Twofish::new(key_material).unwrap();
+    /// assert_eq!(Twofish::new(b"too short").unwrap_err().length(), 9);
+    /// ```
+    pub fn new(key: &[u8]) -> Result<Twofish, KeyLengthError> {
+        match key.len() {
+            16 => Ok(Twofish::new128(array_ref!(key, 0, 16))),
+            24 => Ok(Twofish::new192(array_ref!(key, 0, 24))),
+            32 => Ok(Twofish::new256(array_ref!(key, 0, 32))),
+            len => Err(KeyLengthError { len }),
+        }
+    }
+    /// Check a raw key for a few obviously bad patterns, the kind that
+    /// placeholder keys are made of. This is a heuristic lint, meant to stop
+    /// `[0; 32]` from shipping to production; it is *not* an estimate of how
+    /// much entropy the key has. A key that passes can still be terrible.
+    ///
+    /// The key isn't used for anything, so its length doesn't matter.
+    ///
+    /// ```rust
+    /// # use lsx::twofish::{Twofish, KeyQuality};
+    /// assert_eq!(Twofish::key_quality(&[0; 16]), KeyQuality::AllZero);
+    /// assert_eq!(Twofish::key_quality(b"0123456789:;<=>?"),
+    ///            KeyQuality::Sequential);
+    /// assert_eq!(Twofish::key_quality(b"Sixteen byte key"), KeyQuality::Ok);
+    /// ```
+    pub fn key_quality(key: &[u8]) -> KeyQuality {
+        let first = match key.first() {
+            Some(&x) => x,
+            None => return KeyQuality::AllZero,
+        };
+        if key.iter().all(|&x| x == first) {
+            if first == 0 { KeyQuality::AllZero }
+            else { KeyQuality::AllSame }
+        }
+        else if key.windows(2).all(|w| w[1] == w[0].wrapping_add(1))
+            || key.windows(2).all(|w| w[1] == w[0].wrapping_sub(1)) {
+            KeyQuality::Sequential
+        }
+        else { KeyQuality::Ok }
+    }
+    /// Returns the block size, in bytes. This is always `BLOCKBYTES`; the
+    /// method exists for the convenience of code that only has a value to
+    /// work with, not a type.
+    pub fn block_size(&self) -> usize {
+        BLOCKBYTES
+    }
     /// Encrypt a single block.
     pub fn encrypt(&self, i: &[u8; 16], o: &mut [u8; 16]) {
         // whiten input
@@ -199,6 +481,107 @@ impl Twofish {
         o[8..12].copy_from_slice(&(r0^self.w[6]).to_le_bytes()[..]);
         o[12..16].copy_from_slice(&(r1^self.w[7]).to_le_bytes()[..]);
     }
+    /// Encrypt a single block, in place.
+    pub fn encrypt_in_place(&self, block: &mut [u8; 16]) {
+        let input = *block;
+        self.encrypt(&input, block)
+    }
+    /// Encrypt four consecutive blocks (64 bytes, the size of a SHA-256
+    /// block), each independently of the others (i.e. in ECB mode). The
+    /// result is the same as four calls to `encrypt`, but the four blocks go
+    /// through the rounds side by side, which gives the CPU independent work
+    /// to overlap. See the warning on `Twofish`.
+    pub fn encrypt_4blocks(&self, input: &[u8; 64], out: &mut [u8; 64]) {
+        // whiten input
+        let mut r = [[0u32; 4]; 4];
+        for (block, r) in r.iter_mut().enumerate() {
+            for (n, r) in r.iter_mut().enumerate() {
+                *r = u32::from_le_bytes(*array_ref!(input, block*16+n*4, 4))
+                    ^ self.w[n];
+            }
+        }
+        // four times around, four times as fast (hopefully)
+        for round in (0 .. 32).step_by(4) {
+            for r in r.iter_mut() {
+                let t0 = g(&self.s, r[0]);
+                let t1 = g(&self.s, r[1].rotate_left(8));
+                let fr0 = t0.wrapping_add(t1).wrapping_add(self.k[round]);
+                let fr1 = t0.wrapping_add(t1 << 1)
+                    .wrapping_add(self.k[round+1]);
+                r[2] = (r[2]^fr0).rotate_right(1);
+                r[3] = r[3].rotate_left(1)^fr1;
+            }
+            for r in r.iter_mut() {
+                let t0 = g(&self.s, r[2]);
+                let t1 = g(&self.s, r[3].rotate_left(8));
+                let fr0 = t0.wrapping_add(t1).wrapping_add(self.k[round+2]);
+                let fr1 = t0.wrapping_add(t1 << 1)
+                    .wrapping_add(self.k[round+3]);
+                r[0] = (r[0]^fr0).rotate_right(1);
+                r[1] = r[1].rotate_left(1)^fr1;
+            }
+        }
+        // whiten output and ... output it
+        for (o, r) in out.chunks_exact_mut(BLOCKBYTES).zip(r.iter()) {
+            o[0..4].copy_from_slice(&(r[2]^self.w[4]).to_le_bytes()[..]);
+            o[4..8].copy_from_slice(&(r[3]^self.w[5]).to_le_bytes()[..]);
+            o[8..12].copy_from_slice(&(r[0]^self.w[6]).to_le_bytes()[..]);
+            o[12..16].copy_from_slice(&(r[1]^self.w[7]).to_le_bytes()[..]);
+        }
+    }
+    /// Encrypt a single block, scattering the output across two slices (e.g.
+    /// the two halves of a ring buffer that wrapped around). As much of the
+    /// ciphertext as will fit goes into `out_a`, and the rest goes into
+    /// `out_b`. Panics if the two slices are less than `BLOCKBYTES` long in
+    /// total.
+    pub fn encrypt_into2(&self, i: &[u8; 16], out_a: &mut [u8],
+                         out_b: &mut [u8]) {
+        assert!(out_a.len() + out_b.len() >= BLOCKBYTES);
+        let mut o = [0u8; BLOCKBYTES];
+        self.encrypt(i, &mut o);
+        let split = out_a.len().min(BLOCKBYTES);
+        out_a[.. split].copy_from_slice(&o[.. split]);
+        out_b[.. BLOCKBYTES - split].copy_from_slice(&o[split ..]);
+    }
+    /// Encrypt many blocks, in place, each independently of the others (i.e.
+    /// in ECB mode). The same as `encrypt_blocks`. Panics if the length of
+    /// `data` is not a multiple of `BLOCKBYTES`.
+    pub fn encrypt_bulk(&self, data: &mut [u8]) {
+        self.encrypt_blocks(data)
+    }
+    /// Encrypt many blocks, in place, each independently of the others (i.e.
+    /// in ECB mode). Runs of four blocks go through `encrypt_4blocks`, so
+    /// this is quite a bit faster than calling `encrypt` in a loop. See the
+    /// warning on `Twofish`. Panics if the length of `data` is not a multiple
+    /// of `BLOCKBYTES`.
+    pub fn encrypt_blocks(&self, data: &mut [u8]) {
+        assert_eq!(data.len() % BLOCKBYTES, 0);
+        let mut quads = data.chunks_exact_mut(BLOCKBYTES * 4);
+        for quad in &mut quads {
+            let input = *array_ref!(quad, 0, BLOCKBYTES * 4);
+            self.encrypt_4blocks(&input,
+                                 array_mut_ref!(quad, 0, BLOCKBYTES * 4));
+        }
+        for block in quads.into_remainder().chunks_exact_mut(BLOCKBYTES) {
+            self.encrypt_in_place(array_mut_ref!(block, 0, BLOCKBYTES));
+        }
+    }
+    /// Decrypt many blocks, in place, each independently of the others. The
+    /// inverse of `encrypt_blocks`, and like it, runs of four blocks go
+    /// through `decrypt_4blocks`. Panics if the length of `data` is not a
+    /// multiple of `BLOCKBYTES`.
+    pub fn decrypt_blocks(&self, data: &mut [u8]) {
+        assert_eq!(data.len() % BLOCKBYTES, 0);
+        let mut quads = data.chunks_exact_mut(BLOCKBYTES * 4);
+        for quad in &mut quads {
+            let input = *array_ref!(quad, 0, BLOCKBYTES * 4);
+            self.decrypt_4blocks(&input,
+                                 array_mut_ref!(quad, 0, BLOCKBYTES * 4));
+        }
+        for block in quads.into_remainder().chunks_exact_mut(BLOCKBYTES) {
+            self.decrypt_in_place(array_mut_ref!(block, 0, BLOCKBYTES));
+        }
+    }
     /// Decrypt a single block.
     pub fn decrypt(&self, i: &[u8; 16], o: &mut [u8; 16]) {
         // whiten input
@@ -222,13 +605,182 @@ impl Twofish {
         o[8..12].copy_from_slice(&(r2^self.w[2]).to_le_bytes()[..]);
         o[12..16].copy_from_slice(&(r3^self.w[3]).to_le_bytes()[..]);
     }
+    /// Decrypt a single block, in place.
+    pub fn decrypt_in_place(&self, block: &mut [u8; 16]) {
+        let input = *block;
+        self.decrypt(&input, block)
+    }
+    /// Decrypt four consecutive blocks (64 bytes), each independently of the
+    /// others. The inverse of `encrypt_4blocks`.
+    pub fn decrypt_4blocks(&self, input: &[u8; 64], out: &mut [u8; 64]) {
+        // whiten input
+        let mut r = [[0u32; 4]; 4];
+        for (block, r) in r.iter_mut().enumerate() {
+            for (n, &reg) in [2, 3, 0, 1].iter().enumerate() {
+                r[reg] = u32::from_le_bytes(*array_ref!(input, block*16+n*4,
+                                                        4))
+                    ^ self.w[4 + n];
+            }
+        }
+        // same rounds as encrypt_4blocks, backwards
+        for round in (0 .. 32).step_by(4).rev() {
+            for r in r.iter_mut() {
+                let t0 = g(&self.s, r[2]);
+                let t1 = g(&self.s, r[3].rotate_left(8));
+                let fr0 = t0.wrapping_add(t1).wrapping_add(self.k[round+2]);
+                let fr1 = t0.wrapping_add(t1 << 1)
+                    .wrapping_add(self.k[round+3]);
+                r[0] = r[0].rotate_left(1)^fr0;
+                r[1] = (r[1]^fr1).rotate_right(1);
+            }
+            for r in r.iter_mut() {
+                let t0 = g(&self.s, r[0]);
+                let t1 = g(&self.s, r[1].rotate_left(8));
+                let fr0 = t0.wrapping_add(t1).wrapping_add(self.k[round]);
+                let fr1 = t0.wrapping_add(t1 << 1)
+                    .wrapping_add(self.k[round+1]);
+                r[2] = r[2].rotate_left(1)^fr0;
+                r[3] = (r[3]^fr1).rotate_right(1);
+            }
+        }
+        // whiten output
+        for (o, r) in out.chunks_exact_mut(BLOCKBYTES).zip(r.iter()) {
+            for (n, r) in r.iter().enumerate() {
+                o[n*4 .. n*4+4].copy_from_slice(&(r^self.w[n]).to_le_bytes());
+            }
+        }
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::Zeroize for Twofish {
+    fn zeroize(&mut self) {
+        self.s.zeroize();
+        self.w.zeroize();
+        self.k.zeroize();
+    }
+}
+
+/// Two `Twofish`es are equal if they have the same key schedule, i.e. if
+/// they encrypt exactly the same way. In practice, that means they were made
+/// from the same key. The whole key schedule is always compared, without
+/// branching on its contents, so this doesn't reveal where two schedules
+/// differ.
+///
+/// ```rust
+/// # use lsx::twofish::Twofish;
+/// assert_eq!(Twofish::new128(&[1; 16]), Twofish::new128(&[1; 16]));
+/// assert_ne!(Twofish::new128(&[1; 16]), Twofish::new128(&[2; 16]));
+/// ```
+impl PartialEq for Twofish {
+    fn eq(&self, other: &Twofish) -> bool {
+        let mut diff = 0u32;
+        for (a, b) in self.s.iter().flatten().zip(other.s.iter().flatten())
+            .chain(self.w.iter().zip(other.w.iter()))
+            .chain(self.k.iter().zip(other.k.iter())) {
+            diff |= a ^ b;
+        }
+        core::hint::black_box(diff) == 0
+    }
 }
 
-impl std::fmt::Debug for Twofish {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl Eq for Twofish {}
+
+/// The same as [`Twofish::new`].
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, KeyLengthError};
+/// use std::convert::TryFrom;
+/// fn encrypt_one(key: &[u8], block: &mut [u8; 16])
+///     -> Result<(), KeyLengthError> {
+///     Twofish::try_from(key)?.encrypt_in_place(block);
+///     Ok(())
+/// }
+/// let mut block = [0u8; 16];
+/// assert!(encrypt_one(b"Sixteen byte key", &mut block).is_ok());
+/// assert_eq!(encrypt_one(b"Seventeen byte key", &mut block).unwrap_err()
+///            .length(), 18);
+/// ```
+impl core::convert::TryFrom<&[u8]> for Twofish {
+    type Error = KeyLengthError;
+    fn try_from(key: &[u8]) -> Result<Twofish, KeyLengthError> {
+        Twofish::new(key)
+    }
+}
+
+impl core::fmt::Debug for Twofish {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
         write!(fmt, "Twofish {{ ... }}")
     }
 }
 
+/// A `Twofish` that wipes its key schedule when it goes out of scope.
+/// Requires the `"zeroize"` feature.
+///
+/// Unlike `Twofish`, this isn't `Copy`, so it can implement `Drop`. It
+/// dereferences to `Twofish`, so it can be used anywhere a `&Twofish` is
+/// wanted, including all the modes. Only the schedule inside the wrapper is
+/// wiped; if you wrap an existing `Twofish`, that one is left alone, so
+/// prefer making it with `ZeroizingTwofish::new`.
+///
+/// ```rust
+/// # use lsx::twofish::ZeroizingTwofish;
+/// let twofish = ZeroizingTwofish::new(b"Sixteen byte key").unwrap();
+/// let mut block = *b"Sixteen bytes!!!";
+/// twofish.encrypt_in_place(&mut block);
+/// twofish.decrypt_in_place(&mut block);
+/// assert_eq!(&block, b"Sixteen bytes!!!");
+/// // the key schedule is wiped here
+/// ```
+#[cfg(feature="zeroize")]
+#[derive(Clone)]
+pub struct ZeroizingTwofish(Twofish);
+
+#[cfg(feature="zeroize")]
+impl ZeroizingTwofish {
+    /// Set up a key schedule for the given 128-, 192-, or 256-bit key, the
+    /// same as [`Twofish::new`].
+    pub fn new(key: &[u8]) -> Result<ZeroizingTwofish, KeyLengthError> {
+        Twofish::new(key).map(ZeroizingTwofish)
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl From<Twofish> for ZeroizingTwofish {
+    fn from(twofish: Twofish) -> ZeroizingTwofish {
+        ZeroizingTwofish(twofish)
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl core::ops::Deref for ZeroizingTwofish {
+    type Target = Twofish;
+    fn deref(&self) -> &Twofish {
+        &self.0
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl Drop for ZeroizingTwofish {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+        self.0.zeroize();
+        #[cfg(test)]
+        tests::note_drop(&self.0);
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl zeroize::ZeroizeOnDrop for ZeroizingTwofish {}
+
+#[cfg(feature="zeroize")]
+impl core::fmt::Debug for ZeroizingTwofish {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "ZeroizingTwofish {{ ... }}")
+    }
+}
+
+// (the ECB known-answer tests are older than these lints)
 #[cfg(test)]
+#[allow(clippy::op_ref, clippy::print_with_newline)]
 mod tests;
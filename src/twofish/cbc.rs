@@ -0,0 +1,194 @@
+//! [Cipher block chaining][1] (CBC) mode, built on the Twofish primitive.
+//!
+//! [1]: https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#Cipher_block_chaining_(CBC)
+
+use super::{Twofish, BLOCKBYTES};
+
+/// Encrypts blocks in CBC mode. Each plaintext block is XORed with the
+/// previous ciphertext block (or, for the first block, the IV) before being
+/// encrypted.
+#[derive(Clone)]
+pub struct CbcEncryptor<'a> {
+    cipher: &'a Twofish,
+    iv: [u8; BLOCKBYTES],
+}
+
+/// Decrypts blocks in CBC mode. The inverse of
+/// [`CbcEncryptor`](struct.CbcEncryptor.html).
+#[derive(Clone)]
+pub struct CbcDecryptor<'a> {
+    cipher: &'a Twofish,
+    iv: [u8; BLOCKBYTES],
+}
+
+fn xor_block(a: &mut [u8; BLOCKBYTES], b: &[u8; BLOCKBYTES]) {
+    for (a, b) in a.iter_mut().zip(b.iter()) { *a ^= b }
+}
+
+impl<'a> CbcEncryptor<'a> {
+    /// Set up CBC encryption with the given cipher and IV.
+    pub fn new(cipher: &'a Twofish, iv: &[u8; BLOCKBYTES])
+        -> CbcEncryptor<'a> {
+        CbcEncryptor { cipher, iv: *iv }
+    }
+    /// Start over with a new IV, as if this were a freshly constructed
+    /// `CbcEncryptor` using the same cipher.
+    pub fn reset_iv(&mut self, iv: &[u8; BLOCKBYTES]) {
+        self.iv = *iv;
+    }
+    /// Encrypt the next block, in place.
+    pub fn encrypt_block(&mut self, block: &mut [u8; BLOCKBYTES]) {
+        xor_block(&mut self.iv, block);
+        self.cipher.encrypt(&self.iv, block);
+        self.iv = *block;
+    }
+}
+
+impl<'a> CbcDecryptor<'a> {
+    /// Set up CBC decryption with the given cipher and IV.
+    pub fn new(cipher: &'a Twofish, iv: &[u8; BLOCKBYTES])
+        -> CbcDecryptor<'a> {
+        CbcDecryptor { cipher, iv: *iv }
+    }
+    /// Start over with a new IV, as if this were a freshly constructed
+    /// `CbcDecryptor` using the same cipher.
+    pub fn reset_iv(&mut self, iv: &[u8; BLOCKBYTES]) {
+        self.iv = *iv;
+    }
+    /// Decrypt the next block, in place.
+    pub fn decrypt_block(&mut self, block: &mut [u8; BLOCKBYTES]) {
+        let ciphertext = *block;
+        self.cipher.decrypt(&ciphertext, block);
+        xor_block(block, &self.iv);
+        self.iv = ciphertext;
+    }
+}
+
+impl core::fmt::Debug for CbcEncryptor<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "CbcEncryptor {{ ... }}")
+    }
+}
+
+impl core::fmt::Debug for CbcDecryptor<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "CbcDecryptor {{ ... }}")
+    }
+}
+
+/// The error returned by [`cbc_decrypt_unpad`](fn.cbc_decrypt_unpad.html).
+/// Deliberately tells you nothing about *what* went wrong.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct CbcError;
+
+impl core::fmt::Display for CbcError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "CBC decryption failed")
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for CbcError {}
+
+/// Returns 0xFF if `a < b`, 0 otherwise. Both must be less than 2^31.
+fn ct_lt_mask(a: u32, b: u32) -> u8 {
+    (a.wrapping_sub(b) >> 31).wrapping_neg() as u8
+}
+
+/// Check the PKCS#7 padding of the final block, without branching on any of
+/// its contents. Returns the number of padding bytes, and 0xFF if the padding
+/// was invalid (0 otherwise).
+fn check_pkcs7(last: &[u8; BLOCKBYTES]) -> (usize, u8) {
+    let pad = last[BLOCKBYTES-1] as u32;
+    // invalid if pad == 0 or pad > BLOCKBYTES
+    let mut bad = ct_lt_mask(pad, 1) | ct_lt_mask(BLOCKBYTES as u32, pad);
+    for (i, &byte) in last.iter().enumerate() {
+        // the last `pad` bytes must all be equal to `pad`
+        let in_pad = ct_lt_mask((BLOCKBYTES - 1 - i) as u32, pad);
+        let differs = ct_lt_mask(0, (byte as u32) ^ pad);
+        bad |= in_pad & differs;
+    }
+    (pad as usize, bad)
+}
+
+/// Decrypt a CBC-encrypted, PKCS#7-padded message, returning the plaintext
+/// with the padding removed. Requires the `"alloc"` feature (enabled by
+/// `"std"`).
+///
+/// The padding is checked in constant time, and every kind of failure (bad
+/// length, bad padding) returns the same, opaque `CbcError`. This denies an
+/// attacker the "padding oracle" they would need to decrypt your messages by
+/// trial and error. Don't undo this by reporting failures in more detail
+/// elsewhere! (Better still, authenticate your ciphertext before you decrypt
+/// it.)
+// (`is_multiple_of` is only stable since Rust 1.87)
+#[cfg(feature="alloc")]
+#[allow(clippy::manual_is_multiple_of)]
+pub fn cbc_decrypt_unpad(cipher: &Twofish, iv: &[u8; BLOCKBYTES],
+                         ciphertext: &[u8])
+                         -> Result<alloc::vec::Vec<u8>, CbcError> {
+    if ciphertext.is_empty() || ciphertext.len() % BLOCKBYTES != 0 {
+        return Err(CbcError)
+    }
+    let mut plaintext = ciphertext.to_vec();
+    let mut decryptor = CbcDecryptor::new(cipher, iv);
+    for block in plaintext.chunks_exact_mut(BLOCKBYTES) {
+        decryptor.decrypt_block(array_mut_ref!(block, 0, BLOCKBYTES));
+    }
+    let len = unpad_pkcs7(&plaintext).map_err(|_| CbcError)?.len();
+    plaintext.truncate(len);
+    Ok(plaintext)
+}
+
+/// The error returned by [`unpad_pkcs7`](fn.unpad_pkcs7.html). Like
+/// `CbcError`, it doesn't say what was wrong with the padding.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct PaddingError;
+
+impl core::fmt::Display for PaddingError {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "invalid PKCS#7 padding")
+    }
+}
+
+#[cfg(feature="std")]
+impl std::error::Error for PaddingError {}
+
+/// Append PKCS#7 padding to `buf`: between 1 and `BLOCKBYTES` bytes, each
+/// equal to the number of bytes added, bringing the length up to a multiple
+/// of `BLOCKBYTES`. A buffer that's already a multiple gets a whole block of
+/// padding. Requires the `"alloc"` feature (enabled by `"std"`).
+///
+/// ```rust
+/// # use lsx::twofish;
+/// let mut buf = b"Thirteen byte".to_vec();
+/// twofish::pad_pkcs7(&mut buf);
+/// assert_eq!(&buf[..], b"Thirteen byte\x03\x03\x03");
+/// assert_eq!(twofish::unpad_pkcs7(&buf), Ok(&b"Thirteen byte"[..]));
+/// ```
+#[cfg(feature="alloc")]
+pub fn pad_pkcs7(buf: &mut alloc::vec::Vec<u8>) {
+    let pad = BLOCKBYTES - buf.len() % BLOCKBYTES;
+    buf.resize(buf.len() + pad, pad as u8);
+}
+
+/// Check and remove the PKCS#7 padding from `buf`, returning the data that
+/// came before it. The padding bytes are checked in constant time, and every
+/// kind of failure (a length that isn't a positive multiple of `BLOCKBYTES`,
+/// a padding length of zero or more than `BLOCKBYTES`, padding bytes that
+/// don't match) returns the same `PaddingError`. See the warning on
+/// [`cbc_decrypt_unpad`](fn.cbc_decrypt_unpad.html) about padding oracles.
+// (`is_multiple_of` is only stable since Rust 1.87)
+#[allow(clippy::manual_is_multiple_of)]
+pub fn unpad_pkcs7(buf: &[u8]) -> Result<&[u8], PaddingError> {
+    if buf.is_empty() || buf.len() % BLOCKBYTES != 0 {
+        return Err(PaddingError)
+    }
+    let (pad, bad) = check_pkcs7(array_ref!(buf, buf.len() - BLOCKBYTES,
+                                            BLOCKBYTES));
+    if bad != 0 { return Err(PaddingError) }
+    Ok(&buf[.. buf.len() - pad])
+}
+
+#[cfg(test)]
+mod tests;
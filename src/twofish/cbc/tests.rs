@@ -0,0 +1,143 @@
+use super::*;
+
+#[cfg(feature="alloc")]
+fn encrypt_raw(cipher: &Twofish, iv: &[u8; BLOCKBYTES], data: &[u8])
+    -> Vec<u8> {
+    let mut ret = data.to_vec();
+    let mut encryptor = CbcEncryptor::new(cipher, iv);
+    for block in ret.chunks_exact_mut(BLOCKBYTES) {
+        encryptor.encrypt_block(array_mut_ref!(block, 0, BLOCKBYTES));
+    }
+    ret
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn decrypt_unpad_valid() {
+    let cipher = Twofish::new192(&[0x11; 24]);
+    let iv = [0x22; BLOCKBYTES];
+    for len in 0 .. BLOCKBYTES * 3 {
+        let message: Vec<u8> = (0 .. len as u8).collect();
+        let pad = BLOCKBYTES - len % BLOCKBYTES;
+        let mut padded = message.clone();
+        padded.resize(len + pad, pad as u8);
+        let ciphertext = encrypt_raw(&cipher, &iv, &padded);
+        assert_eq!(cbc_decrypt_unpad(&cipher, &iv, &ciphertext),
+                   Ok(message));
+    }
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn decrypt_unpad_malicious() {
+    let cipher = Twofish::new192(&[0x11; 24]);
+    let iv = [0x22; BLOCKBYTES];
+    let mut bad_blocks = vec![
+        [0u8; BLOCKBYTES], // zero padding byte
+        [17u8; BLOCKBYTES], // padding longer than a block
+        [255u8; BLOCKBYTES], // padding much longer than a block
+    ];
+    for pad in 2 ..= BLOCKBYTES {
+        // one wrong byte inside the padding
+        let mut block = [pad as u8; BLOCKBYTES];
+        block[BLOCKBYTES - pad] ^= 1;
+        bad_blocks.push(block);
+    }
+    for block in bad_blocks {
+        let ciphertext = encrypt_raw(&cipher, &iv, &block[..]);
+        assert_eq!(cbc_decrypt_unpad(&cipher, &iv, &ciphertext),
+                   Err(CbcError));
+    }
+    assert_eq!(cbc_decrypt_unpad(&cipher, &iv, &[]), Err(CbcError));
+    assert_eq!(cbc_decrypt_unpad(&cipher, &iv, &[0; BLOCKBYTES+1]),
+               Err(CbcError));
+}
+
+#[test]
+pub fn reset_iv() {
+    let cipher = Twofish::new128(&[0x33; 16]);
+    let mut encryptor = CbcEncryptor::new(&cipher, &[1; BLOCKBYTES]);
+    let mut decryptor = CbcDecryptor::new(&cipher, &[1; BLOCKBYTES]);
+    let mut block = [0x44; BLOCKBYTES];
+    encryptor.encrypt_block(&mut block);
+    decryptor.decrypt_block(&mut block);
+    encryptor.reset_iv(&[2; BLOCKBYTES]);
+    decryptor.reset_iv(&[2; BLOCKBYTES]);
+    let mut reset = [[0x55; BLOCKBYTES]; 2];
+    let mut fresh = reset;
+    let mut fresh_encryptor = CbcEncryptor::new(&cipher, &[2; BLOCKBYTES]);
+    for (reset, fresh) in reset.iter_mut().zip(fresh.iter_mut()) {
+        encryptor.encrypt_block(reset);
+        fresh_encryptor.encrypt_block(fresh);
+    }
+    assert_eq!(reset, fresh);
+    let mut fresh_decryptor = CbcDecryptor::new(&cipher, &[2; BLOCKBYTES]);
+    for (reset, fresh) in reset.iter_mut().zip(fresh.iter_mut()) {
+        decryptor.decrypt_block(reset);
+        fresh_decryptor.decrypt_block(fresh);
+    }
+    assert_eq!(reset, fresh);
+    assert_eq!(reset, [[0x55; BLOCKBYTES]; 2]);
+}
+
+#[test]
+pub fn known_vector() {
+    // With an all-zero key, IV and plaintext, each CBC step encrypts the
+    // previous ciphertext, which is exactly how the first entries of the
+    // 128-bit table in "ecb_tbl.txt" were made.
+    const CIPHERTEXT: [[u8; BLOCKBYTES]; 2] = [
+        [0x9F,0x58,0x9F,0x5C,0xF6,0x12,0x2C,0x32,
+         0xB6,0xBF,0xEC,0x2F,0x2A,0xE8,0xC3,0x5A],
+        [0xD4,0x91,0xDB,0x16,0xE7,0xB1,0xC3,0x9E,
+         0x86,0xCB,0x08,0x6B,0x78,0x9F,0x54,0x19],
+    ];
+    let cipher = Twofish::new128(&[0; 16]);
+    let mut encryptor = CbcEncryptor::new(&cipher, &[0; BLOCKBYTES]);
+    let mut blocks = [[0u8; BLOCKBYTES]; 2];
+    for block in blocks.iter_mut() { encryptor.encrypt_block(block) }
+    assert_eq!(blocks, CIPHERTEXT);
+    let mut decryptor = CbcDecryptor::new(&cipher, &[0; BLOCKBYTES]);
+    for block in blocks.iter_mut() { decryptor.decrypt_block(block) }
+    assert_eq!(blocks, [[0u8; BLOCKBYTES]; 2]);
+    // starting from the middle, with the first ciphertext block as the IV
+    let mut encryptor = CbcEncryptor::new(&cipher, &CIPHERTEXT[0]);
+    let mut block = [0u8; BLOCKBYTES];
+    encryptor.encrypt_block(&mut block);
+    assert_eq!(block, CIPHERTEXT[1]);
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn pkcs7_round_trip() {
+    for len in 0 .. BLOCKBYTES * 3 {
+        let message: Vec<u8> = (0 .. len as u8).collect();
+        let mut padded = message.clone();
+        pad_pkcs7(&mut padded);
+        let pad = padded.len() - len;
+        // always 1 to BLOCKBYTES bytes, so a full block gets a whole block
+        assert!((1 ..= BLOCKBYTES).contains(&pad));
+        assert_eq!(padded.len() % BLOCKBYTES, 0);
+        assert!(padded[len ..].iter().all(|&x| x as usize == pad));
+        assert_eq!(unpad_pkcs7(&padded), Ok(&message[..]));
+    }
+}
+
+#[test]
+pub fn pkcs7_invalid() {
+    assert_eq!(unpad_pkcs7(&[]), Err(PaddingError));
+    assert_eq!(unpad_pkcs7(&[1; BLOCKBYTES - 1]), Err(PaddingError));
+    assert_eq!(unpad_pkcs7(&[1; BLOCKBYTES + 1]), Err(PaddingError));
+    assert_eq!(unpad_pkcs7(&[0; BLOCKBYTES]), Err(PaddingError));
+    assert_eq!(unpad_pkcs7(&[17; BLOCKBYTES]), Err(PaddingError));
+    assert_eq!(unpad_pkcs7(&[255; BLOCKBYTES * 2]), Err(PaddingError));
+    for pad in 2 ..= BLOCKBYTES {
+        let mut block = [pad as u8; BLOCKBYTES];
+        assert_eq!(unpad_pkcs7(&block), Ok(&block[.. BLOCKBYTES - pad]));
+        block[BLOCKBYTES - pad] ^= 1;
+        assert_eq!(unpad_pkcs7(&block), Err(PaddingError));
+    }
+    // only the last block's padding counts
+    let mut blocks = [0u8; BLOCKBYTES * 2];
+    blocks[BLOCKBYTES * 2 - 1] = 1;
+    assert_eq!(unpad_pkcs7(&blocks), Ok(&blocks[.. BLOCKBYTES * 2 - 1]));
+}
@@ -0,0 +1,251 @@
+//! [Counter with CBC-MAC][1] (CCM) mode, an authenticated encryption mode
+//! built out of Twofish-CTR and a CBC-MAC over the plaintext, as specified
+//! for AES in [NIST SP 800-38C][2].
+//!
+//! Like EAX, CCM needs nothing but the block cipher, which makes it popular
+//! on small devices, and every block goes through the cipher twice. Unlike
+//! EAX, the nonce and tag lengths are chosen from a fixed menu, and the
+//! length of the message goes into the first block of the MAC, so the whole
+//! message has to be at hand before encryption can start.
+//!
+//! [1]: https://en.wikipedia.org/wiki/CCM_mode
+//! [2]: https://csrc.nist.gov/publications/detail/sp/800-38c/final
+
+use super::{Twofish, Ctr, BLOCKBYTES, AuthenticationError, cmac::ct_eq};
+
+/// A CBC-MAC calculation in progress. Unlike `Cmac`, there's no final
+/// tweak; the caller pads to a block boundary whenever CCM says to.
+struct CbcMac<'a> {
+    cipher: &'a Twofish,
+    y: [u8; BLOCKBYTES],
+    pos: usize,
+}
+
+impl CbcMac<'_> {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = (BLOCKBYTES - self.pos).min(data.len());
+            for (y, d) in self.y[self.pos .. self.pos + n].iter_mut()
+                .zip(&data[.. n]) {
+                *y ^= d;
+            }
+            self.pos += n;
+            data = &data[n ..];
+            if self.pos == BLOCKBYTES {
+                self.cipher.encrypt_in_place(&mut self.y);
+                self.pos = 0;
+            }
+        }
+    }
+    /// Zero-pad to the next block boundary, if we aren't on one already.
+    fn pad(&mut self) {
+        if self.pos != 0 {
+            self.cipher.encrypt_in_place(&mut self.y);
+            self.pos = 0;
+        }
+    }
+}
+
+/// Twofish in CCM mode: authenticates the nonce, the associated data, and
+/// the plaintext with a CBC-MAC, then encrypts the plaintext and the MAC with
+/// Twofish-CTR.
+///
+/// `T` is the tag length in bytes, which must be 4, 6, 8, 10, 12, 14, or 16.
+/// The nonce length is chosen at runtime, and must be from 7 to 13 bytes.
+/// Longer nonces leave fewer bytes for the message length: with a 13-byte
+/// nonce, messages are limited to 64KiB. See `max_message_bytes`.
+///
+/// Short tags are easier to forge; a 4-byte tag can be guessed one time in
+/// 2^32. Only use them if every byte on the wire really counts.
+///
+/// Never, ever use the same key and nonce to encrypt two different messages!
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, Ccm};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let ccm = Ccm::<8>::new(&twofish, 13);
+/// let nonce = [0u8; 13];
+/// let mut data = *b"Hi, Bob!";
+/// let tag = ccm.encrypt_in_place_detached(&nonce, b"To: Bob", &mut data);
+/// assert!(ccm.decrypt_in_place_detached(&nonce, b"To: Eve", &mut data,
+///                                       &tag).is_err());
+/// ccm.decrypt_in_place_detached(&nonce, b"To: Bob", &mut data, &tag)
+///     .unwrap();
+/// assert_eq!(&data, b"Hi, Bob!");
+/// ```
+#[derive(Clone,Copy)]
+pub struct Ccm<'a, const T: usize> {
+    cipher: &'a Twofish,
+    nonce_len: usize,
+}
+
+impl<'a, const T: usize> Ccm<'a, T> {
+    /// Set up CCM mode with the given cipher and nonce length.
+    ///
+    /// Panics if `T` isn't a valid tag length (an even number from 4 to 16),
+    /// or `nonce_len` isn't from 7 to 13.
+    pub fn new(cipher: &'a Twofish, nonce_len: usize) -> Ccm<'a, T> {
+        assert!(matches!(T, 4 | 6 | 8 | 10 | 12 | 14 | 16),
+                "CCM tags must be 4, 6, 8, 10, 12, 14, or 16 bytes long");
+        assert!((7 ..= 13).contains(&nonce_len),
+                "CCM nonces must be from 7 to 13 bytes long");
+        Ccm { cipher, nonce_len }
+    }
+    /// Return the nonce length, in bytes.
+    pub fn nonce_len(&self) -> usize {
+        self.nonce_len
+    }
+    /// Return the longest message this nonce length allows, in bytes. The
+    /// bytes the nonce doesn't use (15 minus its length) hold the message
+    /// length, so this is 2^16 - 1 with a 13-byte nonce, 2^24 - 1 with a
+    /// 12-byte nonce, and so on.
+    pub fn max_message_bytes(&self) -> u64 {
+        match self.length_bytes() {
+            8 => u64::MAX,
+            q => (1 << (q * 8)) - 1,
+        }
+    }
+    /// The number of bytes in the length field (the spec's `q`).
+    fn length_bytes(&self) -> usize {
+        BLOCKBYTES - 1 - self.nonce_len
+    }
+    fn check_nonce(&self, nonce: &[u8]) {
+        assert_eq!(nonce.len(), self.nonce_len,
+                   "CCM nonce is the wrong length");
+    }
+    /// A block holding the nonce and `value` (a counter, or the message
+    /// length), with the flags byte set for a counter block.
+    fn counter_block(&self, nonce: &[u8], value: u64) -> [u8; BLOCKBYTES] {
+        let q = self.length_bytes();
+        let mut block = [0; BLOCKBYTES];
+        block[0] = (q - 1) as u8;
+        block[1 .. 1 + self.nonce_len].copy_from_slice(nonce);
+        block[BLOCKBYTES - q ..]
+            .copy_from_slice(&value.to_be_bytes()[8 - q ..]);
+        block
+    }
+    /// Calculate the (encrypted) tag for the given nonce, associated data,
+    /// and plaintext.
+    fn tag(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> [u8; T] {
+        let mut mac = CbcMac { cipher: self.cipher, y: [0; BLOCKBYTES],
+                               pos: 0 };
+        let mut b0 = self.counter_block(nonce, plaintext.len() as u64);
+        b0[0] |= ((T as u8 - 2) / 2) << 3;
+        if !aad.is_empty() { b0[0] |= 0x40 }
+        mac.update(&b0);
+        if !aad.is_empty() {
+            let len = aad.len() as u64;
+            if len < 0xFF00 {
+                mac.update(&(len as u16).to_be_bytes());
+            }
+            else if len <= u32::MAX as u64 {
+                mac.update(&[0xFF, 0xFE]);
+                mac.update(&(len as u32).to_be_bytes());
+            }
+            else {
+                mac.update(&[0xFF, 0xFF]);
+                mac.update(&len.to_be_bytes());
+            }
+            mac.update(aad);
+            mac.pad();
+        }
+        mac.update(plaintext);
+        mac.pad();
+        let mut mask = [0; BLOCKBYTES];
+        self.cipher.encrypt(&self.counter_block(nonce, 0), &mut mask);
+        let mut tag = [0; T];
+        for ((t, y), m) in tag.iter_mut().zip(mac.y.iter()).zip(mask.iter()) {
+            *t = y ^ m;
+        }
+        tag
+    }
+    /// Encrypt or decrypt `data` in place.
+    fn apply_keystream(&self, nonce: &[u8], data: &mut [u8]) {
+        Ctr::new(self.cipher, &self.counter_block(nonce, 1))
+            .apply_keystream(data);
+    }
+    /// Encrypt `data` in place, and return the tag that authenticates it
+    /// along with the nonce and `aad` (associated data, which is
+    /// authenticated but not encrypted). `aad` may be empty.
+    ///
+    /// The ciphertext and tag are the same ones `encrypt` returns, but the
+    /// ciphertext is written over `data` instead of into a new `Vec`, so
+    /// this works without the `"alloc"` feature. The tag is yours to store
+    /// wherever you like.
+    ///
+    /// Panics if the nonce is the wrong length, or `data` is longer than
+    /// `max_message_bytes`.
+    pub fn encrypt_in_place_detached(&self, nonce: &[u8], aad: &[u8],
+                                     data: &mut [u8]) -> [u8; T] {
+        self.check_nonce(nonce);
+        assert!(data.len() as u64 <= self.max_message_bytes(),
+                "message is too long for CCM with this nonce length");
+        let tag = self.tag(nonce, aad, data);
+        self.apply_keystream(nonce, data);
+        tag
+    }
+    /// Decrypt `data` in place, and check its tag, the nonce, and `aad` in
+    /// constant time. If the tag is wrong, `data` is put back the way it was
+    /// and `AuthenticationError` is returned.
+    ///
+    /// The in-place, allocation-free version of `decrypt`. Ciphertexts and
+    /// tags from `encrypt` and `encrypt_in_place_detached` are
+    /// interchangeable.
+    ///
+    /// Panics if the nonce is the wrong length.
+    pub fn decrypt_in_place_detached(&self, nonce: &[u8], aad: &[u8],
+                                     data: &mut [u8], tag: &[u8; T])
+                                     -> Result<(), AuthenticationError> {
+        self.check_nonce(nonce);
+        if data.len() as u64 > self.max_message_bytes() {
+            return Err(AuthenticationError)
+        }
+        // CCM authenticates the plaintext, so we have to decrypt first
+        self.apply_keystream(nonce, data);
+        let mut expected = [0; BLOCKBYTES];
+        let mut actual = [0; BLOCKBYTES];
+        expected[.. T].copy_from_slice(&self.tag(nonce, aad, data));
+        actual[.. T].copy_from_slice(tag);
+        if !ct_eq(&expected, &actual) {
+            self.apply_keystream(nonce, data);
+            return Err(AuthenticationError)
+        }
+        Ok(())
+    }
+    /// Encrypt `plaintext`, returning the ciphertext (the same length as the
+    /// plaintext) and the tag that authenticates it along with the nonce and
+    /// `aad`. Requires the `"alloc"` feature (enabled by `"std"`).
+    ///
+    /// Panics if the nonce is the wrong length, or `plaintext` is longer
+    /// than `max_message_bytes`.
+    #[cfg(feature="alloc")]
+    pub fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8])
+                   -> (alloc::vec::Vec<u8>, [u8; T]) {
+        let mut ciphertext = plaintext.to_vec();
+        let tag = self.encrypt_in_place_detached(nonce, aad,
+                                                 &mut ciphertext);
+        (ciphertext, tag)
+    }
+    /// Decrypt `ciphertext`, and check its tag, the nonce, and `aad` in
+    /// constant time. If it's right, return the plaintext. Requires the
+    /// `"alloc"` feature (enabled by `"std"`).
+    ///
+    /// Panics if the nonce is the wrong length.
+    #[cfg(feature="alloc")]
+    pub fn decrypt(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8],
+                   tag: &[u8; T])
+                   -> Result<alloc::vec::Vec<u8>, AuthenticationError> {
+        let mut plaintext = ciphertext.to_vec();
+        self.decrypt_in_place_detached(nonce, aad, &mut plaintext, tag)?;
+        Ok(plaintext)
+    }
+}
+
+impl<const T: usize> core::fmt::Debug for Ccm<'_, T> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Ccm {{ ... }}")
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,157 @@
+use super::*;
+use crate::tests::unhex;
+
+fn check_vector<const T: usize>(key: &[u8], nonce: &[u8], aad: &[u8],
+                                plaintext: &[u8], ciphertext: &str,
+                                tag: &str) {
+    let twofish = Twofish::new(key).unwrap();
+    let ccm = Ccm::<T>::new(&twofish, nonce.len());
+    let mut buf = plaintext.to_vec();
+    let t = ccm.encrypt_in_place_detached(nonce, aad, &mut buf);
+    assert_eq!(buf, unhex(ciphertext));
+    assert_eq!(t[..], unhex(tag)[..]);
+    ccm.decrypt_in_place_detached(nonce, aad, &mut buf, &t).unwrap();
+    assert_eq!(buf, plaintext);
+}
+
+#[test]
+pub fn known_vectors() {
+    // computed with an independent implementation of Twofish and CCM (the
+    // latter checked against AES-CCM)
+    let key: Vec<u8> = (0 .. 32).collect();
+    let nonce: Vec<u8> = (0 .. 32).collect();
+    check_vector::<16>(&[0; 16], &[0; 13], b"", b"",
+                       "", "dd50edb8be4701fa303c1cba7fe05198");
+    check_vector::<8>(&key[.. 16], &nonce[.. 7], b"header", b"",
+                      "", "65c87bb6586dd79f");
+    check_vector::<4>(&key[.. 24], b"nonce-12byte", b"", b"Attack at dawn!!",
+                      "7cdceb224c9c5e7a8003e1bc38963419", "fcc7c1f2");
+    check_vector::<10>(&key, &nonce[16 .. 29], b"To: Bob, From: Alice",
+                       b"The quick brown fox jumps over the lazy dog",
+                       "7d785a367b476c729d3874de9e8291654991b9c86769c72bf9\
+                        9eb9d08a2659e54129957cdc8ad24319ec93",
+                       "c4b6207e29b59d4e2168");
+    // associated data long enough to need the six-byte length encoding
+    let aad: Vec<u8> = (0 ..= 255).cycle().take(76800).collect();
+    check_vector::<16>(&key[.. 16], &nonce[.. 10], &aad, b"x",
+                       "0e", "755720af03ec1134ebf70bd018437eea");
+}
+
+#[cfg(feature="alloc")]
+fn round_trip<const T: usize>() {
+    let twofish = Twofish::new256(&[0xA5; 32]);
+    let data: Vec<u8> = (0 .. 100).collect();
+    for nonce_len in 7 ..= 13 {
+        let ccm = Ccm::<T>::new(&twofish, nonce_len);
+        for len in 0 .. data.len() {
+            let nonce = &data[len / 2 ..][.. nonce_len];
+            let aad = &data[.. len / 3];
+            let mut buf = data[.. len].to_vec();
+            let tag = ccm.encrypt_in_place_detached(nonce, aad, &mut buf);
+            assert_eq!(ccm.encrypt(nonce, aad, &data[.. len]),
+                       (buf.clone(), tag));
+            assert_eq!(ccm.decrypt(nonce, aad, &buf, &tag).unwrap(),
+                       &data[.. len]);
+        }
+    }
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn ccm_round_trip() {
+    round_trip::<4>();
+    round_trip::<6>();
+    round_trip::<8>();
+    round_trip::<10>();
+    round_trip::<12>();
+    round_trip::<14>();
+    round_trip::<16>();
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn ccm_detached_matches_combined() {
+    let twofish = Twofish::new192(&[0x3C; 24]);
+    let ccm = Ccm::<12>::new(&twofish, 11);
+    let nonce = b"a nonce!!!!";
+    let plaintext = b"Attack at dawn, or maybe a bit later.";
+    let (ciphertext, tag) = ccm.encrypt(nonce, b"header", plaintext);
+    let mut buf = plaintext.to_vec();
+    assert_eq!(ccm.encrypt_in_place_detached(nonce, b"header", &mut buf),
+               tag);
+    assert_eq!(buf, ciphertext);
+    // and each can open what the other sealed
+    ccm.decrypt_in_place_detached(nonce, b"header", &mut buf, &tag).unwrap();
+    assert_eq!(buf, plaintext);
+    let mut buf = plaintext.to_vec();
+    let tag = ccm.encrypt_in_place_detached(nonce, b"header", &mut buf);
+    assert_eq!(ccm.decrypt(nonce, b"header", &buf, &tag).unwrap(),
+               plaintext);
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn ccm_tampering() {
+    let twofish = Twofish::new128(&[0x33; 16]);
+    let ccm = Ccm::<8>::new(&twofish, 11);
+    let nonce = b"nonce-11byt";
+    let (ciphertext, tag) = ccm.encrypt(nonce, b"header", b"Meet at noon.");
+    for n in 0 .. ciphertext.len() {
+        let mut bad = ciphertext.clone();
+        bad[n] ^= 1;
+        assert_eq!(ccm.decrypt(nonce, b"header", &bad, &tag),
+                   Err(AuthenticationError));
+        // a failed decryption must leave the data alone
+        let mut buf = bad.clone();
+        assert!(ccm.decrypt_in_place_detached(nonce, b"header", &mut buf,
+                                              &tag).is_err());
+        assert_eq!(buf, bad);
+    }
+    for n in 0 .. tag.len() {
+        let mut bad = tag;
+        bad[n] ^= 0x80;
+        assert!(ccm.decrypt(nonce, b"header", &ciphertext, &bad).is_err());
+    }
+    assert!(ccm.decrypt(b"nonce-11byT", b"header", &ciphertext, &tag)
+            .is_err());
+    assert!(ccm.decrypt(nonce, b"Header", &ciphertext, &tag).is_err());
+    assert!(ccm.decrypt(nonce, b"", &ciphertext, &tag).is_err());
+    assert!(ccm.decrypt(nonce, b"header", &ciphertext[1 ..], &tag).is_err());
+    // the tag length is authenticated too
+    let ccm16 = Ccm::<16>::new(&twofish, 11);
+    let (_, tag16) = ccm16.encrypt(nonce, b"header", b"Meet at noon.");
+    assert_ne!(tag16[.. 8], tag[..]);
+}
+
+#[test]
+pub fn ccm_message_limit() {
+    let twofish = Twofish::new128(&[0x12; 16]);
+    assert_eq!(Ccm::<16>::new(&twofish, 13).max_message_bytes(), 0xFFFF);
+    assert_eq!(Ccm::<16>::new(&twofish, 12).max_message_bytes(), 0xFFFFFF);
+    assert_eq!(Ccm::<16>::new(&twofish, 7).max_message_bytes(), u64::MAX);
+    let ccm = Ccm::<16>::new(&twofish, 13);
+    let mut data = [0u8; 0x10000];
+    assert!(ccm.decrypt_in_place_detached(&[0; 13], b"", &mut data,
+                                          &[0; 16]).is_err());
+    assert_eq!(data[..], [0u8; 0x10000][..]);
+}
+
+#[test]
+#[should_panic]
+pub fn ccm_bad_tag_length() {
+    let _ = Ccm::<5>::new(&Twofish::new128(&[0; 16]), 13);
+}
+
+#[test]
+#[should_panic]
+pub fn ccm_bad_nonce_length() {
+    let _ = Ccm::<16>::new(&Twofish::new128(&[0; 16]), 14);
+}
+
+#[test]
+#[should_panic]
+pub fn ccm_wrong_nonce() {
+    let twofish = Twofish::new128(&[0; 16]);
+    let ccm = Ccm::<16>::new(&twofish, 13);
+    let _ = ccm.encrypt_in_place_detached(&[0; 12], b"", &mut []);
+}
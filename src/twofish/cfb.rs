@@ -0,0 +1,87 @@
+//! [Cipher feedback][1] (CFB) mode, built on the Twofish primitive. This is
+//! full-block (128-bit) CFB: the feedback is a whole block of ciphertext.
+//!
+//! [1]: https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#Cipher_feedback_(CFB)
+
+use super::{Twofish, BLOCKBYTES};
+
+/// A Twofish-CFB state. Each block of plaintext is XORed with the encryption
+/// of the previous block of ciphertext (or, for the first block, the IV).
+/// Unlike CTR and OFB, the ciphertext feeds back into the keystream, so
+/// encryption and decryption are different operations.
+///
+/// Data need not be provided in whole blocks, but don't mix `encrypt` and
+/// `decrypt` on the same state.
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, Cfb};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let iv = [0u8; 16];
+/// let mut data = *b"Attack at dawn!! Or maybe a little after.";
+/// Cfb::new(&twofish, &iv).encrypt(&mut data[..]);
+/// assert_ne!(&data[..], b"Attack at dawn!! Or maybe a little after.");
+/// Cfb::new(&twofish, &iv).decrypt(&mut data[..]);
+/// assert_eq!(&data[..], b"Attack at dawn!! Or maybe a little after.");
+/// ```
+#[derive(Clone)]
+pub struct Cfb<'a> {
+    cipher: &'a Twofish,
+    register: [u8; BLOCKBYTES],
+    keystream: [u8; BLOCKBYTES],
+    pos: usize,
+}
+
+impl<'a> Cfb<'a> {
+    /// Set up CFB mode with the given cipher and IV.
+    pub fn new(cipher: &'a Twofish, iv: &[u8; BLOCKBYTES]) -> Cfb<'a> {
+        Cfb {
+            cipher,
+            register: *iv,
+            keystream: [0u8; BLOCKBYTES],
+            pos: BLOCKBYTES,
+        }
+    }
+    /// Start over with a new IV, as if this were a freshly constructed `Cfb`
+    /// using the same cipher.
+    pub fn reset_iv(&mut self, iv: &[u8; BLOCKBYTES]) {
+        self.register = *iv;
+        self.pos = BLOCKBYTES;
+    }
+    /// Return the next byte of keystream, and where the corresponding byte of
+    /// ciphertext goes in the feedback register.
+    fn next_keystream(&mut self) -> (u8, usize) {
+        if self.pos == BLOCKBYTES {
+            // the register now holds a whole block of ciphertext (or the IV)
+            self.cipher.encrypt(&self.register, &mut self.keystream);
+            self.pos = 0;
+        }
+        let pos = self.pos;
+        self.pos += 1;
+        (self.keystream[pos], pos)
+    }
+    /// Encrypt the next `data.len()` bytes, in place.
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let (k, pos) = self.next_keystream();
+            *byte ^= k;
+            self.register[pos] = *byte;
+        }
+    }
+    /// Decrypt the next `data.len()` bytes, in place.
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let (k, pos) = self.next_keystream();
+            self.register[pos] = *byte;
+            *byte ^= k;
+        }
+    }
+}
+
+impl core::fmt::Debug for Cfb<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Cfb {{ ... }}")
+    }
+}
+
+#[cfg(test)]
+mod tests;
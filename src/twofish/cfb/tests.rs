@@ -0,0 +1,62 @@
+use super::*;
+
+#[test]
+pub fn cfb_known_vector() {
+    // With an all-zero key, IV and plaintext, each block of ciphertext is the
+    // encryption of the one before, just like the first entries of the
+    // 128-bit table in "ecb_tbl.txt".
+    const CIPHERTEXT: [u8; BLOCKBYTES * 2] = [
+        0x9F,0x58,0x9F,0x5C,0xF6,0x12,0x2C,0x32,
+        0xB6,0xBF,0xEC,0x2F,0x2A,0xE8,0xC3,0x5A,
+        0xD4,0x91,0xDB,0x16,0xE7,0xB1,0xC3,0x9E,
+        0x86,0xCB,0x08,0x6B,0x78,0x9F,0x54,0x19,
+    ];
+    let cipher = Twofish::new128(&[0; 16]);
+    for chunksize in &[1, 5, 16, 17, 32] {
+        let mut data = [0u8; BLOCKBYTES * 2];
+        let mut cfb = Cfb::new(&cipher, &[0; BLOCKBYTES]);
+        for chunk in data.chunks_mut(*chunksize) { cfb.encrypt(chunk) }
+        assert_eq!(data, CIPHERTEXT);
+        let mut cfb = Cfb::new(&cipher, &[0; BLOCKBYTES]);
+        for chunk in data.chunks_mut(*chunksize) { cfb.decrypt(chunk) }
+        assert_eq!(data, [0u8; BLOCKBYTES * 2]);
+    }
+}
+
+#[test]
+pub fn cfb_round_trip() {
+    let cipher = Twofish::new256(&[0x3C; 32]);
+    let iv = [0xA5; BLOCKBYTES];
+    for len in 0 .. BLOCKBYTES * 3 + 1 {
+        let plaintext: Vec<u8> = (0 .. len).map(|x| (x * 7) as u8).collect();
+        let mut data = plaintext.clone();
+        Cfb::new(&cipher, &iv).encrypt(&mut data[..]);
+        if len > 0 { assert_ne!(data, plaintext) }
+        // each ciphertext block must feed back into the next
+        let mut register = iv;
+        for (c, p) in data.chunks(BLOCKBYTES)
+            .zip(plaintext.chunks(BLOCKBYTES)) {
+            let mut keystream = [0u8; BLOCKBYTES];
+            cipher.encrypt(&register, &mut keystream);
+            for ((c, p), k) in c.iter().zip(p).zip(&keystream) {
+                assert_eq!(*c, p ^ k);
+            }
+            register[.. c.len()].copy_from_slice(c);
+        }
+        Cfb::new(&cipher, &iv).decrypt(&mut data[..]);
+        assert_eq!(data, plaintext);
+    }
+}
+
+#[test]
+pub fn cfb_reset_iv() {
+    let cipher = Twofish::new128(&[0x5A; 16]);
+    let mut cfb = Cfb::new(&cipher, &[1; BLOCKBYTES]);
+    cfb.encrypt(&mut [0u8; 21][..]);
+    cfb.reset_iv(&[2; BLOCKBYTES]);
+    let mut reset = [0u8; 40];
+    cfb.encrypt(&mut reset[..]);
+    let mut fresh = [0u8; 40];
+    Cfb::new(&cipher, &[2; BLOCKBYTES]).encrypt(&mut fresh[..]);
+    assert_eq!(reset, fresh);
+}
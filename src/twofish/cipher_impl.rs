@@ -0,0 +1,39 @@
+//! Implementations of the [`cipher`][1] crate's traits, so that `Twofish` can
+//! be plugged into the RustCrypto block modes. Requires the `"cipher"`
+//! feature.
+//!
+//! `KeyInit::new` takes a 256-bit key, but `KeyInit::new_from_slice` accepts
+//! 128-, 192-, and 256-bit keys, just like [`Twofish::new`].
+//!
+//! [1]: https://docs.rs/cipher/0.4
+
+use super::{Twofish, BLOCKBYTES};
+use cipher::{consts::{U16, U32}, InvalidLength, Key, KeyInit, KeySizeUser};
+
+cipher::impl_simple_block_encdec!(
+    Twofish, U16, state, block,
+    encrypt: {
+        let input = *array_ref!(block.get_in(), 0, BLOCKBYTES);
+        state.encrypt(&input, array_mut_ref!(block.get_out(), 0, BLOCKBYTES));
+    }
+    decrypt: {
+        let input = *array_ref!(block.get_in(), 0, BLOCKBYTES);
+        state.decrypt(&input, array_mut_ref!(block.get_out(), 0, BLOCKBYTES));
+    }
+);
+
+// (the macro doesn't implement this marker, but the block modes want it)
+impl cipher::BlockCipher for Twofish {}
+
+impl KeySizeUser for Twofish {
+    type KeySize = U32;
+}
+
+impl KeyInit for Twofish {
+    fn new(key: &Key<Twofish>) -> Twofish {
+        Twofish::new256(array_ref!(key, 0, 32))
+    }
+    fn new_from_slice(key: &[u8]) -> Result<Twofish, InvalidLength> {
+        Twofish::new(key).map_err(|_| InvalidLength)
+    }
+}
@@ -0,0 +1,136 @@
+//! [CMAC][1] (a.k.a. OMAC1), a message authentication code built on the
+//! Twofish primitive, as specified for AES in [RFC 4493][2].
+//!
+//! [1]: https://en.wikipedia.org/wiki/One-key_MAC
+//! [2]: https://www.rfc-editor.org/rfc/rfc4493
+
+use super::{Twofish, BLOCKBYTES};
+
+/// Multiply a block by x in GF(2^128), as CMAC does to derive its subkeys.
+fn dbl(block: &[u8; BLOCKBYTES]) -> [u8; BLOCKBYTES] {
+    let x = u128::from_be_bytes(*block);
+    // constant time: 0x87 if the top bit was set, 0 otherwise
+    let carry = (x >> 127) as u8;
+    ((x << 1) ^ (0x87 & 0u128.wrapping_sub(carry as u128))).to_be_bytes()
+}
+
+/// Compare two tags in constant time.
+pub(super) fn ct_eq(a: &[u8; BLOCKBYTES], b: &[u8; BLOCKBYTES]) -> bool {
+    let mut diff = 0u8;
+    for (a, b) in a.iter().zip(b.iter()) {
+        diff |= a ^ b;
+    }
+    core::hint::black_box(diff) == 0
+}
+
+/// A Twofish-CMAC calculation in progress. Data can be provided in pieces of
+/// any size.
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, Cmac};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let mut cmac = Cmac::new(&twofish);
+/// cmac.update(b"Hello ");
+/// cmac.update(b"World!");
+/// let tag = cmac.finish();
+/// assert!(lsx::twofish::verify_only(&twofish, b"Hello World!", &tag));
+/// ```
+#[derive(Clone)]
+pub struct Cmac<'a> {
+    cipher: &'a Twofish,
+    state: [u8; BLOCKBYTES],
+    buf: [u8; BLOCKBYTES],
+    buffered_bytes: usize,
+}
+
+impl<'a> Cmac<'a> {
+    /// Start a new CMAC calculation with the given cipher.
+    pub fn new(cipher: &'a Twofish) -> Cmac<'a> {
+        Cmac {
+            cipher,
+            state: [0; BLOCKBYTES],
+            buf: [0; BLOCKBYTES],
+            buffered_bytes: 0,
+        }
+    }
+    /// Fold the (full) buffer into the state.
+    fn process_buf(&mut self) {
+        for (s, b) in self.state.iter_mut().zip(self.buf.iter()) {
+            *s ^= b;
+        }
+        let input = self.state;
+        self.cipher.encrypt(&input, &mut self.state);
+        self.buffered_bytes = 0;
+    }
+    /// Authenticate some more data.
+    pub fn update(&mut self, mut data: &[u8]) {
+        // The last block is special, so we hold on to a full buffer until we
+        // know that more data is coming.
+        while !data.is_empty() {
+            if self.buffered_bytes == BLOCKBYTES { self.process_buf() }
+            let amount = data.len().min(BLOCKBYTES - self.buffered_bytes);
+            self.buf[self.buffered_bytes .. self.buffered_bytes + amount]
+                .copy_from_slice(&data[.. amount]);
+            self.buffered_bytes += amount;
+            data = &data[amount ..];
+        }
+    }
+    /// Finish the calculation, returning the tag.
+    pub fn finish(mut self) -> [u8; BLOCKBYTES] {
+        let mut l = [0; BLOCKBYTES];
+        self.cipher.encrypt(&[0; BLOCKBYTES], &mut l);
+        let k1 = dbl(&l);
+        let subkey = if self.buffered_bytes == BLOCKBYTES { k1 }
+        else {
+            self.buf[self.buffered_bytes] = 0x80;
+            for b in self.buf[self.buffered_bytes + 1 ..].iter_mut() {
+                *b = 0;
+            }
+            dbl(&k1)
+        };
+        for (b, k) in self.buf.iter_mut().zip(subkey.iter()) {
+            *b ^= k;
+        }
+        self.process_buf();
+        self.state
+    }
+    /// Finish the calculation, and compare the tag against the expected one
+    /// in constant time.
+    pub fn verify(self, tag: &[u8; BLOCKBYTES]) -> bool {
+        ct_eq(&self.finish(), tag)
+    }
+}
+
+impl core::fmt::Debug for Cmac<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Cmac {{ ... }}")
+    }
+}
+
+/// Authenticate `data` without encrypting it, returning a tag to send along
+/// with it. This is plain Twofish-CMAC, but shaped like the rest of the
+/// authenticated modes, for messages that only need integrity.
+///
+/// ```rust
+/// # use lsx::twofish::{self, Twofish};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let tag = twofish::mac_only(&twofish, b"Public announcement");
+/// assert!(twofish::verify_only(&twofish, b"Public announcement", &tag));
+/// assert!(!twofish::verify_only(&twofish, b"Public annoucnement", &tag));
+/// ```
+pub fn mac_only(cipher: &Twofish, data: &[u8]) -> [u8; BLOCKBYTES] {
+    let mut cmac = Cmac::new(cipher);
+    cmac.update(data);
+    cmac.finish()
+}
+
+/// Check a tag produced by [`mac_only`](fn.mac_only.html), in constant time.
+pub fn verify_only(cipher: &Twofish, data: &[u8], tag: &[u8; BLOCKBYTES])
+                   -> bool {
+    let mut cmac = Cmac::new(cipher);
+    cmac.update(data);
+    cmac.verify(tag)
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,63 @@
+use super::*;
+
+#[test]
+pub fn subkeys() {
+    // from RFC 4493 section 4 (the subkeys don't depend on the cipher)
+    let l = 0x7df76b0c1ab899b33e42f047b91b546fu128.to_be_bytes();
+    let k1 = dbl(&l);
+    assert_eq!(k1, 0xfbeed618357133667c85e08f7236a8deu128.to_be_bytes());
+    assert_eq!(dbl(&k1), 0xf7ddac306ae266ccf90bc11ee46d513bu128.to_be_bytes());
+}
+
+#[test]
+pub fn structure() {
+    let twofish = Twofish::new192(&[0x3C; 24]);
+    let mut l = [0; BLOCKBYTES];
+    twofish.encrypt(&[0; BLOCKBYTES], &mut l);
+    let k1 = dbl(&l);
+    let k2 = dbl(&k1);
+    // one full block: E(M ^ K1)
+    let message = *b"exactly 16 bytes";
+    let mut block = [0; BLOCKBYTES];
+    for ((b, m), k) in block.iter_mut().zip(message.iter()).zip(k1.iter()) {
+        *b = m ^ k;
+    }
+    let mut expected = [0; BLOCKBYTES];
+    twofish.encrypt(&block, &mut expected);
+    assert_eq!(mac_only(&twofish, &message), expected);
+    // empty message: E(0x80 00 .. 00 ^ K2)
+    let mut block = k2;
+    block[0] ^= 0x80;
+    twofish.encrypt(&block, &mut expected);
+    assert_eq!(mac_only(&twofish, &[]), expected);
+}
+
+#[test]
+pub fn streaming() {
+    let twofish = Twofish::new128(&[0x77; 16]);
+    let data: Vec<u8> = (0 .. 100).collect();
+    for len in 0 .. data.len() {
+        let expected = mac_only(&twofish, &data[.. len]);
+        for chunksize in 1 .. 34 {
+            let mut cmac = Cmac::new(&twofish);
+            for chunk in data[.. len].chunks(chunksize) {
+                cmac.update(chunk);
+            }
+            assert_eq!(cmac.finish(), expected);
+        }
+    }
+}
+
+#[test]
+pub fn verify_only_rejects() {
+    let twofish = Twofish::new256(&[0x11; 32]);
+    let tag = mac_only(&twofish, b"cleartext message");
+    assert!(verify_only(&twofish, b"cleartext message", &tag));
+    assert!(!verify_only(&twofish, b"cleartext messagf", &tag));
+    assert!(!verify_only(&twofish, b"cleartext message!", &tag));
+    for i in 0 .. BLOCKBYTES {
+        let mut bad = tag;
+        bad[i] ^= 1;
+        assert!(!verify_only(&twofish, b"cleartext message", &bad));
+    }
+}
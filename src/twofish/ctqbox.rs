@@ -0,0 +1,116 @@
+//! Table-free versions of the q permutations, the MDS matrix multiply, and
+//! the RS matrix multiply, used by the key schedule when the `"ct-qbox"`
+//! feature is enabled. Nothing in here indexes memory with a secret-derived
+//! value, or branches on one.
+//!
+//! The q permutations are evaluated exactly as they are defined in the
+//! Twofish paper, with the 4-bit t-boxes packed into `u64`s and "looked up"
+//! with a shift. (Variable shifts are constant-time on every CPU I care
+//! about.) The GF(2^8) multiplications are done the long way, with masks
+//! instead of branches.
+//!
+//! All of this makes key setup roughly twenty times slower than the
+//! table-driven version (tens of microseconds per key, rather than one or
+//! two, on a modern x86). Encryption and decryption are unaffected, since
+//! they only ever use the key-dependent S-boxes that the key schedule
+//! produces. (This also means that they still do secret-indexed lookups. This
+//! feature only fixes the key schedule.)
+
+use super::tables::RS_POLY_FROM_EXP;
+
+/// The t-boxes of q0, then q1, packed so that entry `i` is in bits `4i`
+/// through `4i+3`.
+const Q0_T: [u64; 4] = [
+    0x4ACE95B023F6D718, 0xD9076A4F53218BCE,
+    0x17423F8C09D6E5AB, 0xAC5803B9E6214F7D,
+];
+const Q1_T: [u64; 4] = [
+    0x5CA04913E67FDB82, 0x809F5AD673C4B2E1,
+    0xF3B28DE0A96157C4, 0xA802F746ED3C159B,
+];
+
+/// The columns of the MDS matrix.
+const MDS_COLUMNS: [[u8; 4]; 4] = [
+    [0x01, 0x5B, 0xEF, 0xEF],
+    [0xEF, 0xEF, 0x5B, 0x01],
+    [0x5B, 0xEF, 0x01, 0xEF],
+    [0x5B, 0x01, 0xEF, 0x5B],
+];
+/// v(x) = x^8 + x^6 + x^5 + x^3 + 1
+const MDS_POLY: u16 = 0x169;
+/// w(x) = x^8 + x^6 + x^3 + x^2 + 1
+const RS_POLY: u16 = 0x14D;
+
+#[inline(always)]
+fn nibble(t: u64, x: u8) -> u8 {
+    ((t >> ((x & 15) * 4)) & 15) as u8
+}
+
+#[inline(always)]
+fn ror4(x: u8) -> u8 {
+    ((x >> 1) | (x << 3)) & 15
+}
+
+#[inline(always)]
+fn q(t: &[u64; 4], x: u8) -> u8 {
+    let a0 = x >> 4;
+    let b0 = x & 15;
+    let a1 = a0 ^ b0;
+    let b1 = a0 ^ ror4(b0) ^ ((a0 << 3) & 15);
+    let a2 = nibble(t[0], a1);
+    let b2 = nibble(t[1], b1);
+    let a3 = a2 ^ b2;
+    let b3 = a2 ^ ror4(b2) ^ ((a2 << 3) & 15);
+    let a4 = nibble(t[2], a3);
+    let b4 = nibble(t[3], b3);
+    (b4 << 4) | a4
+}
+
+#[inline(always)]
+pub fn q0(x: u8) -> u8 { q(&Q0_T, x) }
+
+#[inline(always)]
+pub fn q1(x: u8) -> u8 { q(&Q1_T, x) }
+
+/// Multiply two elements of GF(2^8), without branching or lookups.
+#[inline(always)]
+fn gf_mul(a: u8, mut b: u8, poly: u16) -> u8 {
+    let mut a = a as u16;
+    let mut ret = 0u16;
+    for _ in 0 .. 8 {
+        ret ^= a & ((b & 1) as u16).wrapping_neg();
+        a <<= 1;
+        a ^= poly & ((a >> 8) & 1).wrapping_neg();
+        b >>= 1;
+    }
+    ret as u8
+}
+
+/// The equivalent of `MDSQ[column][x]`.
+// (`is_multiple_of` is only stable since Rust 1.87)
+#[inline(always)]
+#[allow(clippy::manual_is_multiple_of)]
+pub fn mdsq(column: usize, x: u8) -> u32 {
+    let y = if column % 2 == 0 { q1(x) } else { q0(x) };
+    let m = &MDS_COLUMNS[column];
+    u32::from_le_bytes([
+        gf_mul(m[0], y, MDS_POLY),
+        gf_mul(m[1], y, MDS_POLY),
+        gf_mul(m[2], y, MDS_POLY),
+        gf_mul(m[3], y, MDS_POLY),
+    ])
+}
+
+/// The equivalent of the table-driven `rs_mul_column`. The column values are
+/// still given as exponents, and converted with a table lookup, but they are
+/// constants, not secrets.
+pub fn rs_mul_column(a: u8, b: u8, c: u8, d: u8, s: &mut [u8; 4],
+                     key_byte: u8) {
+    s[0] ^= gf_mul(RS_POLY_FROM_EXP[a as usize], key_byte, RS_POLY);
+    s[1] ^= gf_mul(RS_POLY_FROM_EXP[b as usize], key_byte, RS_POLY);
+    s[2] ^= gf_mul(RS_POLY_FROM_EXP[c as usize], key_byte, RS_POLY);
+    s[3] ^= gf_mul(RS_POLY_FROM_EXP[d as usize], key_byte, RS_POLY);
+}
+
+#[cfg(test)]
+mod tests;
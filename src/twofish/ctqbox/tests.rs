@@ -0,0 +1,26 @@
+use super::*;
+use super::super::tables::*;
+
+#[test]
+pub fn matches_tables() {
+    for x in 0 ..= 255u8 {
+        assert_eq!(q0(x), Q0[x as usize]);
+        assert_eq!(q1(x), Q1[x as usize]);
+        for (column, table) in MDSQ.iter().enumerate() {
+            assert_eq!(mdsq(column, x), table[x as usize]);
+        }
+        for column in 0 .. 8 {
+            let m = array_ref!(RS_MATRIX, column*4, 4);
+            let mut ours = [0u8; 4];
+            rs_mul_column(m[0], m[1], m[2], m[3], &mut ours, x);
+            let mut theirs = [0u8; 4];
+            if x != 0 {
+                let exp = RS_POLY_TO_EXP[(x-1) as usize] as usize;
+                for (theirs, m) in theirs.iter_mut().zip(m.iter()) {
+                    *theirs = RS_POLY_FROM_EXP[exp + *m as usize];
+                }
+            }
+            assert_eq!(ours, theirs);
+        }
+    }
+}
@@ -0,0 +1,461 @@
+//! [Counter][1] (CTR) mode, built on the Twofish primitive.
+//!
+//! The counter block is treated as a single big-endian 128-bit integer, which
+//! is incremented once per block of keystream.
+//!
+//! [1]: https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#Counter_(CTR)
+
+use super::{Twofish, BLOCKBYTES};
+
+/// A Twofish-CTR keystream. Encryption and decryption are the same operation:
+/// XOR the keystream into the data.
+///
+/// Never, ever use the same key and counter to encrypt two different messages!
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, Ctr};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let nonce = [0u8; 16];
+/// let mut data = *b"Attack at dawn!! Or maybe a little after.";
+/// Ctr::new(&twofish, &nonce).apply_keystream(&mut data[..]);
+/// assert_ne!(&data[..], b"Attack at dawn!! Or maybe a little after.");
+/// Ctr::new(&twofish, &nonce).apply_keystream(&mut data[..]);
+/// assert_eq!(&data[..], b"Attack at dawn!! Or maybe a little after.");
+/// ```
+#[derive(Clone)]
+pub struct Ctr<'a> {
+    cipher: &'a Twofish,
+    counter: [u8; BLOCKBYTES],
+    keystream: [u8; BLOCKBYTES],
+    pos: usize,
+    processed: u64,
+}
+
+fn increment(counter: &mut [u8; BLOCKBYTES]) {
+    *counter = u128::from_be_bytes(*counter).wrapping_add(1).to_be_bytes();
+}
+
+impl<'a> Ctr<'a> {
+    /// Set up CTR mode with the given cipher and initial counter block.
+    pub fn new(cipher: &'a Twofish, counter: &[u8; BLOCKBYTES]) -> Ctr<'a> {
+        Ctr {
+            cipher,
+            counter: *counter,
+            keystream: [0u8; BLOCKBYTES],
+            pos: BLOCKBYTES,
+            processed: 0,
+        }
+    }
+    /// Set up CTR mode with the given cipher and initial counter, given as a
+    /// `u128`. The counter block is its big-endian representation, so this is
+    /// the same as `Ctr::new(cipher, &counter.to_be_bytes())`. Like always,
+    /// the counter is incremented as a full 128-bit integer.
+    pub fn from_u128(cipher: &'a Twofish, counter: u128) -> Ctr<'a> {
+        Ctr::new(cipher, &counter.to_be_bytes())
+    }
+    /// Start over with a new initial counter block, as if this were a freshly
+    /// constructed `Ctr` using the same cipher. Any leftover keystream is
+    /// discarded.
+    pub fn reset_nonce(&mut self, nonce: &[u8; BLOCKBYTES]) {
+        self.counter = *nonce;
+        self.pos = BLOCKBYTES;
+        self.processed = 0;
+    }
+    /// Encrypt the current counter into a fresh block of keystream, and
+    /// advance the counter.
+    fn refill(&mut self) {
+        self.cipher.encrypt(&self.counter, &mut self.keystream);
+        increment(&mut self.counter);
+        self.pos = 0;
+    }
+    /// XOR the next `data.len()` bytes of keystream into `data`. Data need not
+    /// be provided in whole blocks; the leftover keystream from one call is
+    /// used by the next. Returns the number of bytes processed, which is
+    /// always `data.len()`.
+    pub fn apply_keystream(&mut self, mut data: &mut [u8]) -> usize {
+        let len = data.len();
+        self.processed += len as u64;
+        if self.pos < BLOCKBYTES {
+            let amount = data.len().min(BLOCKBYTES - self.pos);
+            let (head, tail) = data.split_at_mut(amount);
+            for (d, k) in head.iter_mut()
+                .zip(&self.keystream[self.pos .. self.pos + amount]) {
+                *d ^= k;
+            }
+            self.pos += amount;
+            data = tail;
+        }
+        for chunk in data.chunks_mut(BLOCKBYTES) {
+            self.refill();
+            for (d, k) in chunk.iter_mut().zip(&self.keystream[..]) {
+                *d ^= k;
+            }
+            self.pos = chunk.len();
+        }
+        len
+    }
+    /// Return the next whole block of keystream, i.e. the encryption of the
+    /// current counter, and advance the counter. Any keystream left over from
+    /// an `apply_keystream` call that didn't end on a block boundary is
+    /// skipped.
+    pub fn next_keystream_block(&mut self) -> [u8; BLOCKBYTES] {
+        self.refill();
+        self.pos = BLOCKBYTES;
+        self.processed += BLOCKBYTES as u64;
+        self.keystream
+    }
+    /// XOR keystream into several buffers, in order, exactly as if they were
+    /// one contiguous buffer passed to `apply_keystream`. Handy for
+    /// scatter-gather I/O. Returns the total number of bytes processed.
+    pub fn apply_keystream_vectored(&mut self, bufs: &mut [&mut [u8]])
+                                    -> usize {
+        bufs.iter_mut().map(|buf| self.apply_keystream(buf)).sum()
+    }
+    /// Return the total number of bytes of keystream used so far (since
+    /// construction or the last `reset_nonce`).
+    pub fn bytes_processed(&self) -> u64 {
+        self.processed
+    }
+    /// XOR keystream into `data`, starting from `base_counter`, splitting the
+    /// work across threads with `rayon`. The result is exactly what a fresh
+    /// `Ctr::new(cipher, &base_counter)` would produce. Only this `Ctr`'s
+    /// cipher is used; its own counter and position are left alone. Requires
+    /// the `"rayon"` feature.
+    #[cfg(feature="rayon")]
+    pub fn apply_keystream_parallel(&self, base_counter: [u8; BLOCKBYTES],
+                                    data: &mut [u8]) {
+        use rayon::prelude::*;
+        let base = u128::from_be_bytes(base_counter);
+        data.par_chunks_mut(PARALLEL_CHUNK_BYTES).enumerate()
+            .for_each(|(n, chunk)| {
+                let blocks = (n * (PARALLEL_CHUNK_BYTES / BLOCKBYTES)) as u128;
+                Ctr::from_u128(self.cipher, base.wrapping_add(blocks))
+                    .apply_keystream(chunk);
+            });
+    }
+}
+
+/// How much data each thread takes at a time in `apply_keystream_parallel`.
+/// Must be a multiple of `BLOCKBYTES`.
+#[cfg(feature="rayon")]
+const PARALLEL_CHUNK_BYTES: usize = 16384;
+
+impl core::fmt::Debug for Ctr<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Ctr {{ ... }}")
+    }
+}
+
+/// A deterministic pseudo-random byte generator: the Twofish-CTR keystream,
+/// i.e. CTR mode applied to an endless all-zero plaintext. The same key and
+/// counter always produce the same bytes, which makes this handy for
+/// reproducible test data. With the `"rand"` feature, it implements
+/// `rand_core::RngCore` and `rand_core::SeedableRng`.
+///
+/// This is *not* a replacement for the OS's random number generator. It's
+/// only as unpredictable as its seed.
+///
+/// ```rust
+/// # use lsx::twofish::CtrRng;
+/// let mut a = CtrRng::from_seed(&[42; 32]);
+/// let mut b = CtrRng::from_seed(&[42; 32]);
+/// let mut x = [0u8; 100];
+/// let mut y = [0u8; 100];
+/// a.fill_bytes(&mut x[..]);
+/// b.fill_bytes(&mut y[.. 37]);
+/// b.fill_bytes(&mut y[37 ..]);
+/// assert_eq!(&x[..], &y[..]);
+/// ```
+#[derive(Clone)]
+pub struct CtrRng {
+    cipher: Twofish,
+    counter: [u8; BLOCKBYTES],
+    keystream: [u8; BLOCKBYTES],
+    pos: usize,
+}
+
+impl CtrRng {
+    /// Generate the keystream of the given cipher, starting from the given
+    /// counter block.
+    pub fn new(cipher: Twofish, counter: &[u8; BLOCKBYTES]) -> CtrRng {
+        CtrRng {
+            cipher,
+            counter: *counter,
+            keystream: [0u8; BLOCKBYTES],
+            pos: BLOCKBYTES,
+        }
+    }
+    /// Use the seed as a 256-bit key, and start the counter at zero.
+    pub fn from_seed(seed: &[u8; 32]) -> CtrRng {
+        CtrRng::new(Twofish::new256(seed), &[0; BLOCKBYTES])
+    }
+    /// Fill `dest` with the next `dest.len()` bytes of keystream.
+    pub fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.pos == BLOCKBYTES {
+                self.cipher.encrypt(&self.counter, &mut self.keystream);
+                increment(&mut self.counter);
+                self.pos = 0;
+            }
+            *byte = self.keystream[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+impl core::fmt::Debug for CtrRng {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "CtrRng {{ ... }}")
+    }
+}
+
+#[cfg(feature="rand")]
+impl rand_core::RngCore for CtrRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        CtrRng::fill_bytes(self, dest)
+    }
+    fn try_fill_bytes(&mut self, dest: &mut [u8])
+                      -> Result<(), rand_core::Error> {
+        CtrRng::fill_bytes(self, dest);
+        Ok(())
+    }
+}
+
+#[cfg(feature="rand")]
+impl rand_core::SeedableRng for CtrRng {
+    type Seed = [u8; 32];
+    fn from_seed(seed: [u8; 32]) -> CtrRng {
+        CtrRng::from_seed(&seed)
+    }
+}
+
+/// Calculate the SHA-256 hash of `data`, and encrypt it in place with
+/// Twofish-CTR, in a single pass. The hash returned is the hash of the
+/// *plaintext*, not the ciphertext. Requires the `"sha256"` feature as well.
+///
+/// ```rust
+/// # use lsx::{sha256, twofish::{self, Twofish}};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let mut data = *b"Back me up, back me up!";
+/// let hash = twofish::hash_then_encrypt(&twofish, &[0; 16], &mut data[..]);
+/// assert_eq!(hash, sha256::hash(b"Back me up, back me up!"));
+/// ```
+#[cfg(feature="sha256")]
+pub fn hash_then_encrypt(cipher: &Twofish, nonce: &[u8; BLOCKBYTES],
+                         data: &mut [u8]) -> [u8; crate::sha256::HASHBYTES] {
+    let mut state = EncryptAndHash::new(cipher, nonce);
+    state.process(data);
+    state.finish().0
+}
+
+/// The streaming version of [`hash_then_encrypt`](fn.hash_then_encrypt.html):
+/// hashes plaintext with SHA-256 and encrypts it in place with Twofish-CTR,
+/// one piece at a time, touching each piece only once. The hash doesn't
+/// involve the key at all; it's an ordinary SHA-256 of the plaintext.
+/// Requires the `"sha256"` feature as well.
+///
+/// ```rust
+/// # use lsx::{sha256, twofish::{Twofish, EncryptAndHash}};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let mut state = EncryptAndHash::new(&twofish, &[0; 16]);
+/// let mut data = *b"Back me up, back me up!";
+/// state.process(&mut data[.. 11]);
+/// state.process(&mut data[11 ..]);
+/// let (hash, len) = state.finish();
+/// assert_eq!(hash, sha256::hash(b"Back me up, back me up!"));
+/// assert_eq!(len, 23);
+/// ```
+#[cfg(feature="sha256")]
+#[derive(Clone,Debug)]
+pub struct EncryptAndHash<'a> {
+    ctr: Ctr<'a>,
+    hasher: crate::sha256::BufSha256,
+}
+
+#[cfg(feature="sha256")]
+impl<'a> EncryptAndHash<'a> {
+    /// Start encrypting with the given cipher and initial counter block.
+    pub fn new(cipher: &'a Twofish, nonce: &[u8; BLOCKBYTES])
+               -> EncryptAndHash<'a> {
+        EncryptAndHash {
+            ctr: Ctr::new(cipher, nonce),
+            hasher: crate::sha256::BufSha256::new(),
+        }
+    }
+    /// Hash some more plaintext, then encrypt it in place.
+    pub fn process(&mut self, data: &mut [u8]) {
+        // big enough to amortize the overhead, small enough to stay in cache
+        for chunk in data.chunks_mut(1024) {
+            self.hasher.update(chunk);
+            self.ctr.apply_keystream(chunk);
+        }
+    }
+    /// Finish up, returning the hash of all the plaintext, and how many bytes
+    /// of it there were.
+    pub fn finish(self) -> ([u8; crate::sha256::HASHBYTES], u64) {
+        (self.hasher.finish(&[]), self.ctr.bytes_processed())
+    }
+}
+
+/// How much plaintext `CtrWriter` encrypts at a time.
+#[cfg(feature="std")]
+const WRITER_BUFBYTES: usize = 1024;
+
+/// Encrypts everything written to it with Twofish-CTR, and passes the
+/// ciphertext on to another writer. Since CTR mode decrypts the same way it
+/// encrypts, this can decrypt too. Requires the `"std"` feature (enabled by
+/// default).
+///
+/// Ciphertext that the inner writer hasn't accepted yet is kept in a small
+/// buffer, and written out by the next `write` or `flush`. Make sure to
+/// `flush` (or call `into_inner`) when you're done, or the end of the
+/// ciphertext may be lost.
+///
+/// ```rust
+/// # use std::io::{Read, Write};
+/// # use lsx::twofish::{Twofish, CtrReader, CtrWriter};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let mut writer = CtrWriter::new(Vec::new(), &twofish, &[0; 16]);
+/// writer.write_all(b"Attack at dawn!").unwrap();
+/// let ciphertext = writer.into_inner().unwrap();
+/// let mut reader = CtrReader::new(&ciphertext[..], &twofish, &[0; 16]);
+/// let mut plaintext = String::new();
+/// reader.read_to_string(&mut plaintext).unwrap();
+/// assert_eq!(plaintext, "Attack at dawn!");
+/// ```
+#[cfg(feature="std")]
+pub struct CtrWriter<'a, W: std::io::Write> {
+    inner: W,
+    ctr: Ctr<'a>,
+    buf: [u8; WRITER_BUFBYTES],
+    /// The range of `buf` that's encrypted, but not yet written
+    start: usize,
+    end: usize,
+}
+
+#[cfg(feature="std")]
+impl<'a, W: std::io::Write> CtrWriter<'a, W> {
+    /// Encrypt data on its way to `inner`, using the given cipher and initial
+    /// counter block.
+    pub fn new(inner: W, cipher: &'a Twofish, counter: &[u8; BLOCKBYTES])
+               -> CtrWriter<'a, W> {
+        CtrWriter {
+            inner,
+            ctr: Ctr::new(cipher, counter),
+            buf: [0; WRITER_BUFBYTES],
+            start: 0,
+            end: 0,
+        }
+    }
+    /// Write out any buffered ciphertext.
+    fn write_pending(&mut self) -> std::io::Result<()> {
+        while self.start < self.end {
+            match self.inner.write(&self.buf[self.start .. self.end]) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(n) => self.start += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+    /// Return a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+    /// Return the number of bytes of plaintext encrypted so far.
+    pub fn bytes_processed(&self) -> u64 {
+        self.ctr.bytes_processed()
+    }
+    /// Write out any buffered ciphertext, and return the inner writer.
+    pub fn into_inner(mut self) -> std::io::Result<W> {
+        self.write_pending()?;
+        Ok(self.inner)
+    }
+}
+
+#[cfg(feature="std")]
+impl<W: std::io::Write> std::io::Write for CtrWriter<'_, W> {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.write_pending()?;
+        let n = data.len().min(WRITER_BUFBYTES);
+        self.buf[.. n].copy_from_slice(&data[.. n]);
+        self.ctr.apply_keystream(&mut self.buf[.. n]);
+        self.start = 0;
+        self.end = n;
+        // The plaintext has been accepted either way. If this fails, the
+        // rest gets another try (and the error another chance to surface)
+        // next time.
+        let _ = self.write_pending();
+        Ok(n)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.write_pending()?;
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature="std")]
+impl<W: std::io::Write> core::fmt::Debug for CtrWriter<'_, W> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "CtrWriter {{ ... }}")
+    }
+}
+
+/// Decrypts everything read through it with Twofish-CTR. Since CTR mode
+/// encrypts the same way it decrypts, this can encrypt too. Requires the
+/// `"std"` feature (enabled by default).
+///
+/// See [`CtrWriter`](struct.CtrWriter.html) for an example.
+#[cfg(feature="std")]
+pub struct CtrReader<'a, R: std::io::Read> {
+    inner: R,
+    ctr: Ctr<'a>,
+}
+
+#[cfg(feature="std")]
+impl<'a, R: std::io::Read> CtrReader<'a, R> {
+    /// Decrypt data coming from `inner`, using the given cipher and initial
+    /// counter block.
+    pub fn new(inner: R, cipher: &'a Twofish, counter: &[u8; BLOCKBYTES])
+               -> CtrReader<'a, R> {
+        CtrReader { inner, ctr: Ctr::new(cipher, counter) }
+    }
+    /// Return a reference to the inner reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+    /// Return the number of bytes of ciphertext decrypted so far.
+    pub fn bytes_processed(&self) -> u64 {
+        self.ctr.bytes_processed()
+    }
+    /// Return the inner reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+#[cfg(feature="std")]
+impl<R: std::io::Read> std::io::Read for CtrReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.ctr.apply_keystream(&mut buf[.. n]);
+        Ok(n)
+    }
+}
+
+#[cfg(feature="std")]
+impl<R: std::io::Read> core::fmt::Debug for CtrReader<'_, R> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "CtrReader {{ ... }}")
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,267 @@
+use super::*;
+
+#[test]
+pub fn ctr_keystream() {
+    let twofish = Twofish::new256(&[0xC3; 32]);
+    let mut counter = [0xFFu8; BLOCKBYTES];
+    counter[0] = 0;
+    let mut expected = [0u8; BLOCKBYTES * 3];
+    let mut block_counter = counter;
+    for chunk in expected.chunks_exact_mut(BLOCKBYTES) {
+        twofish.encrypt(&block_counter, array_mut_ref!(chunk, 0, BLOCKBYTES));
+        increment(&mut block_counter);
+    }
+    // the carry must propagate all the way up
+    assert_eq!(block_counter[0], 1);
+    assert_eq!(block_counter[1..BLOCKBYTES-1], [0; BLOCKBYTES-2]);
+    assert_eq!(block_counter[BLOCKBYTES-1], 2);
+    for chunksize in &[1, 5, 16, 17, 48] {
+        let mut data = [0u8; BLOCKBYTES * 3];
+        let mut ctr = Ctr::new(&twofish, &counter);
+        for chunk in data.chunks_mut(*chunksize) {
+            ctr.apply_keystream(chunk);
+        }
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+pub fn ctr_keystream_blocks() {
+    let twofish = Twofish::new128(&[0x5E; 16]);
+    let nonce = [0xFE; BLOCKBYTES];
+    let mut expected = [0x42u8; BLOCKBYTES * 4];
+    Ctr::new(&twofish, &nonce).apply_keystream(&mut expected);
+    let mut data = [0x42u8; BLOCKBYTES * 4];
+    let mut ctr = Ctr::new(&twofish, &nonce);
+    for chunk in data.chunks_exact_mut(BLOCKBYTES) {
+        for (d, k) in chunk.iter_mut().zip(&ctr.next_keystream_block()) {
+            *d ^= k;
+        }
+    }
+    assert_eq!(data, expected);
+    assert_eq!(ctr.bytes_processed(), data.len() as u64);
+    // leftover keystream is skipped, so this lines up with block 2
+    let mut ctr = Ctr::new(&twofish, &nonce);
+    let mut data = [0x42u8; BLOCKBYTES * 4];
+    ctr.apply_keystream(&mut data[.. 5]);
+    ctr.next_keystream_block();
+    ctr.apply_keystream(&mut data[BLOCKBYTES * 2 ..]);
+    assert_eq!(data[.. 5], expected[.. 5]);
+    assert_eq!(data[BLOCKBYTES * 2 ..], expected[BLOCKBYTES * 2 ..]);
+}
+
+#[test]
+pub fn ctr_round_trip() {
+    let twofish = Twofish::new192(&[0x7E; 24]);
+    let nonce = [0x19; BLOCKBYTES];
+    for len in 0 .. BLOCKBYTES * 3 + 1 {
+        let plaintext: Vec<u8> = (0 .. len).map(|x| (x * 13) as u8).collect();
+        let mut ciphertext = plaintext.clone();
+        Ctr::new(&twofish, &nonce).apply_keystream(&mut ciphertext[..]);
+        if len > 0 { assert_ne!(ciphertext, plaintext) }
+        // applying the keystream is its own inverse...
+        let mut decrypted = ciphertext.clone();
+        Ctr::new(&twofish, &nonce).apply_keystream(&mut decrypted[..]);
+        assert_eq!(decrypted, plaintext);
+        // ...and is nothing more than XOR, byte for byte
+        let mut keystream = vec![0u8; len];
+        Ctr::new(&twofish, &nonce).apply_keystream(&mut keystream[..]);
+        for ((c, p), k) in ciphertext.iter().zip(&plaintext).zip(&keystream) {
+            assert_eq!(*c, p ^ k);
+        }
+    }
+}
+
+#[test]
+pub fn ctr_reset_nonce() {
+    let twofish = Twofish::new128(&[0x5A; 16]);
+    let mut ctr = Ctr::new(&twofish, &[1; BLOCKBYTES]);
+    // leave some keystream unused, so we know reset discards it
+    ctr.apply_keystream(&mut [0u8; 21][..]);
+    ctr.reset_nonce(&[2; BLOCKBYTES]);
+    let mut reset = [0u8; 40];
+    ctr.apply_keystream(&mut reset[..]);
+    let mut fresh = [0u8; 40];
+    Ctr::new(&twofish, &[2; BLOCKBYTES]).apply_keystream(&mut fresh[..]);
+    assert_eq!(reset, fresh);
+}
+
+#[test]
+pub fn ctr_from_u128() {
+    let twofish = Twofish::new128(&[0x6B; 16]);
+    for &n in &[0, 1, 0x0123456789ABCDEF, u64::MAX as u128, u128::MAX] {
+        let mut a = [0u8; 50];
+        let mut b = [0u8; 50];
+        Ctr::from_u128(&twofish, n).apply_keystream(&mut a[..]);
+        Ctr::new(&twofish, &n.to_be_bytes()).apply_keystream(&mut b[..]);
+        assert_eq!(a, b);
+    }
+}
+
+#[test]
+#[cfg(feature="sha256")]
+pub fn hash_then_encrypt_matches() {
+    let twofish = Twofish::new128(&[0x3C; 16]);
+    let nonce = [0x99; BLOCKBYTES];
+    let plaintext: Vec<u8> = (0 .. 3000u32).map(|x| x as u8).collect();
+    let mut data = plaintext.clone();
+    let hash = hash_then_encrypt(&twofish, &nonce, &mut data[..]);
+    assert_eq!(hash, crate::sha256::hash(&plaintext[..]));
+    let mut expected = plaintext.clone();
+    Ctr::new(&twofish, &nonce).apply_keystream(&mut expected[..]);
+    assert_eq!(data, expected);
+}
+
+#[test]
+pub fn ctr_bytes_processed() {
+    let twofish = Twofish::new128(&[0x42; 16]);
+    let mut ctr = Ctr::new(&twofish, &[0; BLOCKBYTES]);
+    let mut data = [0u8; 100];
+    assert_eq!(ctr.bytes_processed(), 0);
+    assert_eq!(ctr.apply_keystream(&mut data[.. 7]), 7);
+    assert_eq!(ctr.apply_keystream(&mut data[.. 0]), 0);
+    assert_eq!(ctr.apply_keystream(&mut data[..]), 100);
+    assert_eq!(ctr.bytes_processed(), 107);
+    ctr.reset_nonce(&[1; BLOCKBYTES]);
+    assert_eq!(ctr.bytes_processed(), 0);
+}
+
+#[test]
+#[cfg(feature="sha256")]
+pub fn encrypt_and_hash_chunked() {
+    let twofish = Twofish::new192(&[0x24; 24]);
+    let nonce = [0x99; BLOCKBYTES];
+    let plaintext: Vec<u8> = (0 .. 5000u32).map(|x| (x * 13) as u8).collect();
+    let mut expected = plaintext.clone();
+    let expected_hash = hash_then_encrypt(&twofish, &nonce, &mut expected);
+    for chunksize in &[1, 15, 16, 17, 1024, 1500, 5000] {
+        let mut data = plaintext.clone();
+        let mut state = EncryptAndHash::new(&twofish, &nonce);
+        for chunk in data.chunks_mut(*chunksize) {
+            state.process(chunk);
+        }
+        assert_eq!(state.finish(), (expected_hash, 5000));
+        assert_eq!(data, expected);
+    }
+}
+
+#[test]
+pub fn ctr_vectored() {
+    let twofish = Twofish::new256(&[0x81; 32]);
+    let nonce = [0x18; BLOCKBYTES];
+    let mut expected = [0u8; 77];
+    Ctr::new(&twofish, &nonce).apply_keystream(&mut expected[..]);
+    for &(a_len, b_len) in &[(0, 0), (5, 20), (16, 16), (31, 0), (1, 75)] {
+        let mut data = [0u8; 77];
+        let (a, rest) = data.split_at_mut(a_len);
+        let (b, c) = rest.split_at_mut(b_len);
+        let mut ctr = Ctr::new(&twofish, &nonce);
+        assert_eq!(ctr.apply_keystream_vectored(&mut [a, b, c]), 77);
+        assert_eq!(ctr.bytes_processed(), 77);
+        assert_eq!(data[..], expected[..]);
+    }
+}
+
+/// A writer that only ever accepts a few bytes at a time.
+#[cfg(feature="std")]
+struct Trickle(Vec<u8>);
+
+#[cfg(feature="std")]
+impl std::io::Write for Trickle {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        let n = data.len().min(3);
+        self.0.extend_from_slice(&data[.. n]);
+        Ok(n)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+#[cfg(feature="std")]
+pub fn ctr_writer_reader_round_trip() {
+    use std::io::{Read, Write};
+    let twofish = Twofish::new128(&[0x3C; 16]);
+    let nonce = [0x77; BLOCKBYTES];
+    let plaintext: Vec<u8> = (0 .. 3000u32).map(|x| (x * 11) as u8).collect();
+    let mut expected = plaintext.clone();
+    Ctr::new(&twofish, &nonce).apply_keystream(&mut expected[..]);
+    for &chunksize in &[1, 15, 16, 100, 2500] {
+        let mut writer = CtrWriter::new(Vec::new(), &twofish, &nonce);
+        for chunk in plaintext.chunks(chunksize) {
+            writer.write_all(chunk).unwrap();
+        }
+        assert_eq!(writer.bytes_processed(), plaintext.len() as u64);
+        let ciphertext = writer.into_inner().unwrap();
+        assert_eq!(ciphertext, expected);
+        let mut writer = CtrWriter::new(Trickle(Vec::new()), &twofish,
+                                        &nonce);
+        for chunk in plaintext.chunks(chunksize) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.flush().unwrap();
+        assert_eq!(writer.get_ref().0, expected);
+        let mut reader = CtrReader::new(&ciphertext[..], &twofish, &nonce);
+        let mut decrypted = Vec::new();
+        let mut buf = vec![0; chunksize];
+        loop {
+            let n = reader.read(&mut buf).unwrap();
+            if n == 0 { break }
+            decrypted.extend_from_slice(&buf[.. n]);
+        }
+        assert_eq!(decrypted, plaintext);
+        assert_eq!(reader.bytes_processed(), plaintext.len() as u64);
+    }
+}
+
+#[test]
+pub fn ctr_rng_reproducible() {
+    let twofish = Twofish::new256(&[0x24; 32]);
+    let nonce = [0x42; BLOCKBYTES];
+    let mut expected = [0u8; 100];
+    Ctr::new(&twofish, &nonce).apply_keystream(&mut expected[..]);
+    for chunksize in &[1, 7, 16, 33, 100] {
+        let mut rng = CtrRng::new(Twofish::new256(&[0x24; 32]), &nonce);
+        let mut got = [0u8; 100];
+        for chunk in got.chunks_mut(*chunksize) { rng.fill_bytes(chunk) }
+        assert_eq!(got[..], expected[..]);
+    }
+    let mut a = [0u8; 50];
+    let mut b = [0u8; 50];
+    CtrRng::from_seed(&[1; 32]).fill_bytes(&mut a[..]);
+    CtrRng::from_seed(&[1; 32]).fill_bytes(&mut b[..]);
+    assert_eq!(a[..], b[..]);
+    CtrRng::from_seed(&[2; 32]).fill_bytes(&mut b[..]);
+    assert_ne!(a[..], b[..]);
+}
+
+#[test]
+#[cfg(feature="rand")]
+pub fn ctr_rng_rand_core() {
+    use rand_core::{RngCore, SeedableRng};
+    let mut ours = CtrRng::from_seed(&[3; 32]);
+    let mut theirs = <CtrRng as SeedableRng>::from_seed([3; 32]);
+    let mut bytes = [0u8; 12];
+    ours.fill_bytes(&mut bytes[..]);
+    assert_eq!(theirs.next_u32().to_le_bytes()[..], bytes[.. 4]);
+    assert_eq!(theirs.next_u64().to_le_bytes()[..], bytes[4 ..]);
+}
+
+#[test]
+#[cfg(feature="rayon")]
+pub fn ctr_parallel_matches_serial() {
+    let twofish = Twofish::new128(&[0x8E; 16]);
+    let mut nonce = [0xFF; BLOCKBYTES];
+    nonce[0] = 0x12;
+    let ctr = Ctr::new(&twofish, &[0; BLOCKBYTES]);
+    for &len in &[0, 1, 15, 16, PARALLEL_CHUNK_BYTES - 1,
+                  PARALLEL_CHUNK_BYTES * 5 + 7] {
+        let plaintext: Vec<u8> = (0 .. len).map(|x| (x * 3) as u8).collect();
+        let mut serial = plaintext.clone();
+        Ctr::new(&twofish, &nonce).apply_keystream(&mut serial[..]);
+        let mut parallel = plaintext.clone();
+        ctr.apply_keystream_parallel(nonce, &mut parallel[..]);
+        assert_eq!(serial, parallel);
+    }
+}
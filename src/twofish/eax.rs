@@ -0,0 +1,130 @@
+//! [EAX mode][1], an authenticated encryption mode built out of Twofish-CTR
+//! and Twofish-CMAC, as specified by Bellare, Rogaway, and Wagner.
+//!
+//! EAX is simpler than GCM (it needs nothing but the block cipher), accepts
+//! nonces of any length, and has no message length limit worth mentioning.
+//! It's also about half as fast, since every block goes through the cipher
+//! twice.
+//!
+//! [1]: https://en.wikipedia.org/wiki/EAX_mode
+
+use super::{Twofish, Cmac, Ctr, BLOCKBYTES, AuthenticationError,
+            cmac::ct_eq};
+
+/// The number of bytes in an EAX tag.
+pub const TAGBYTES: usize = BLOCKBYTES;
+
+/// Twofish in EAX mode: encrypts with Twofish-CTR, starting from a counter
+/// derived from the nonce, and authenticates the nonce, the associated data,
+/// and the ciphertext with Twofish-CMAC.
+///
+/// Never, ever use the same key and nonce to encrypt two different messages!
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, Eax};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let eax = Eax::new(&twofish);
+/// let mut data = *b"Hi, Bob!";
+/// let tag = eax.encrypt_in_place_detached(b"message #1", b"To: Bob",
+///                                         &mut data);
+/// assert!(eax.decrypt_in_place_detached(b"message #2", b"To: Bob",
+///                                       &mut data, &tag).is_err());
+/// eax.decrypt_in_place_detached(b"message #1", b"To: Bob", &mut data, &tag)
+///     .unwrap();
+/// assert_eq!(&data, b"Hi, Bob!");
+/// ```
+#[derive(Clone,Copy)]
+pub struct Eax<'a> {
+    cipher: &'a Twofish,
+}
+
+impl<'a> Eax<'a> {
+    /// Set up EAX mode with the given cipher.
+    pub fn new(cipher: &'a Twofish) -> Eax<'a> {
+        Eax { cipher }
+    }
+    /// CMAC of `data`, prefixed with a block holding `domain`, to keep the
+    /// nonce, associated data, and ciphertext MACs apart. (The EAX paper
+    /// calls this OMAC^t.)
+    fn omac(&self, domain: u8, data: &[u8]) -> [u8; BLOCKBYTES] {
+        let mut prefix = [0; BLOCKBYTES];
+        prefix[BLOCKBYTES - 1] = domain;
+        let mut cmac = Cmac::new(self.cipher);
+        cmac.update(&prefix);
+        cmac.update(data);
+        cmac.finish()
+    }
+    /// Calculate the tag, given the nonce's MAC (which is also the initial
+    /// counter), the associated data, and the ciphertext.
+    fn tag(&self, nonce_mac: &[u8; BLOCKBYTES], aad: &[u8],
+           ciphertext: &[u8]) -> [u8; TAGBYTES] {
+        let mut tag = self.omac(1, aad);
+        for ((t, n), c) in tag.iter_mut().zip(nonce_mac.iter())
+            .zip(self.omac(2, ciphertext).iter()) {
+            *t ^= n ^ c;
+        }
+        tag
+    }
+    /// Encrypt `data` in place, and return the tag that authenticates it
+    /// along with the nonce and `aad` (associated data, which is
+    /// authenticated but not encrypted). The nonce and `aad` may be any
+    /// length, including empty.
+    ///
+    /// Unlike `encrypt`, this needs no allocation, and works without the
+    /// `"alloc"` feature. The ciphertext replaces the plaintext, and the tag
+    /// comes back separately, so it can live apart from the ciphertext. Both
+    /// are the same as what `encrypt` returns.
+    pub fn encrypt_in_place_detached(&self, nonce: &[u8], aad: &[u8],
+                                     data: &mut [u8]) -> [u8; TAGBYTES] {
+        let nonce_mac = self.omac(0, nonce);
+        Ctr::new(self.cipher, &nonce_mac).apply_keystream(data);
+        self.tag(&nonce_mac, aad, data)
+    }
+    /// Check the tag of `data`, the nonce, and `aad` in constant time, and,
+    /// if it's right, decrypt `data` in place. If the tag is wrong, `data` is
+    /// left alone and `AuthenticationError` is returned.
+    ///
+    /// This is `decrypt` without the copy, and it will open anything that
+    /// `encrypt` or `encrypt_in_place_detached` sealed.
+    pub fn decrypt_in_place_detached(&self, nonce: &[u8], aad: &[u8],
+                                     data: &mut [u8], tag: &[u8; TAGBYTES])
+                                     -> Result<(), AuthenticationError> {
+        let nonce_mac = self.omac(0, nonce);
+        if !ct_eq(&self.tag(&nonce_mac, aad, data), tag) {
+            return Err(AuthenticationError)
+        }
+        Ctr::new(self.cipher, &nonce_mac).apply_keystream(data);
+        Ok(())
+    }
+    /// Encrypt `plaintext`, returning the ciphertext (the same length as the
+    /// plaintext) and the tag that authenticates it along with the nonce and
+    /// `aad`. Requires the `"alloc"` feature (enabled by `"std"`).
+    #[cfg(feature="alloc")]
+    pub fn encrypt(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8])
+                   -> (alloc::vec::Vec<u8>, [u8; TAGBYTES]) {
+        let mut ciphertext = plaintext.to_vec();
+        let tag = self.encrypt_in_place_detached(nonce, aad,
+                                                 &mut ciphertext);
+        (ciphertext, tag)
+    }
+    /// Check the tag of `ciphertext`, the nonce, and `aad` in constant time,
+    /// and, if it's right, return the decrypted plaintext. Requires the
+    /// `"alloc"` feature (enabled by `"std"`).
+    #[cfg(feature="alloc")]
+    pub fn decrypt(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8],
+                   tag: &[u8; TAGBYTES])
+                   -> Result<alloc::vec::Vec<u8>, AuthenticationError> {
+        let mut plaintext = ciphertext.to_vec();
+        self.decrypt_in_place_detached(nonce, aad, &mut plaintext, tag)?;
+        Ok(plaintext)
+    }
+}
+
+impl core::fmt::Debug for Eax<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Eax {{ ... }}")
+    }
+}
+
+#[cfg(test)]
+mod tests;
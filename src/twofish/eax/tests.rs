@@ -0,0 +1,113 @@
+use super::*;
+use crate::tests::unhex;
+
+/// Key, nonce, associated data, plaintext, ciphertext (hex), tag (hex).
+type Vector = (&'static [u8], &'static [u8], &'static [u8],
+               &'static [u8], &'static str, &'static str);
+
+#[test]
+pub fn known_vectors() {
+    // computed with an independent implementation of Twofish and EAX (the
+    // latter checked against the AES vectors in the EAX paper)
+    let vectors: &[Vector] = &[
+        (&[0; 16], b"", b"", b"",
+         "", "bcbfef72c2a0471b2af314c5856b84f5"),
+        (&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+         &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+         b"header", b"",
+         "", "904716f8ba104df57d4633286682869f"),
+        (&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+           16, 17, 18, 19, 20, 21, 22, 23],
+         b"nonce", b"", b"Attack at dawn!!",
+         "62663817653e715c9840fda6d2a055af",
+         "ca883c968662c209e7ec67b1a6cb4a41"),
+        (&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+           16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31],
+         &[0x62, 0xec, 0x67, 0xf9, 0xc3, 0xa4, 0xa4, 0x07,
+           0xfc, 0xb2, 0xa8, 0xc4, 0x90, 0x31, 0xa8, 0xb3],
+         &[0x6b, 0xfb, 0x91, 0x4f, 0xd0, 0x7e, 0xae, 0x6b],
+         b"The quick brown fox jumps over the lazy dog",
+         "e5cd89cf6d5ff04c428dea1b836c3789eb4ba01f8230cdf1a9759cb0c8d53b10\
+          bc9c096881fd37bb711842",
+         "cb0740dd99a5ed9ee9c34528cfac8b0a"),
+    ];
+    for (key, nonce, aad, plaintext, ciphertext, tag) in vectors {
+        let twofish = Twofish::new(key).unwrap();
+        let eax = Eax::new(&twofish);
+        let mut buf = plaintext.to_vec();
+        let t = eax.encrypt_in_place_detached(nonce, aad, &mut buf);
+        assert_eq!(buf, unhex(ciphertext));
+        assert_eq!(t[..], unhex(tag)[..]);
+        eax.decrypt_in_place_detached(nonce, aad, &mut buf, &t).unwrap();
+        assert_eq!(buf, *plaintext);
+    }
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn eax_round_trip() {
+    let twofish = Twofish::new256(&[0xA5; 32]);
+    let eax = Eax::new(&twofish);
+    let data: Vec<u8> = (0 .. 100).collect();
+    for len in 0 .. data.len() {
+        let nonce = &data[len / 2 ..][.. len % 20];
+        let aad = &data[.. len / 3];
+        let mut buf = data[.. len].to_vec();
+        let tag = eax.encrypt_in_place_detached(nonce, aad, &mut buf);
+        assert_eq!(eax.encrypt(nonce, aad, &data[.. len]), (buf.clone(), tag));
+        eax.decrypt_in_place_detached(nonce, aad, &mut buf, &tag).unwrap();
+        assert_eq!(buf, &data[.. len]);
+    }
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn eax_detached_matches_combined() {
+    let twofish = Twofish::new192(&[0x3C; 24]);
+    let eax = Eax::new(&twofish);
+    let nonce = b"a nonce";
+    let plaintext = b"Attack at dawn, or maybe a bit later.";
+    let (ciphertext, tag) = eax.encrypt(nonce, b"header", plaintext);
+    let mut buf = plaintext.to_vec();
+    assert_eq!(eax.encrypt_in_place_detached(nonce, b"header", &mut buf),
+               tag);
+    assert_eq!(buf, ciphertext);
+    // and each can open what the other sealed
+    eax.decrypt_in_place_detached(nonce, b"header", &mut buf, &tag).unwrap();
+    assert_eq!(buf, plaintext);
+    let mut buf = plaintext.to_vec();
+    let tag = eax.encrypt_in_place_detached(nonce, b"header", &mut buf);
+    assert_eq!(eax.decrypt(nonce, b"header", &buf, &tag).unwrap(),
+               plaintext);
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn eax_tampering() {
+    let twofish = Twofish::new128(&[0x33; 16]);
+    let eax = Eax::new(&twofish);
+    let nonce = b"nonce";
+    let (ciphertext, tag) = eax.encrypt(nonce, b"header", b"Meet at noon.");
+    for n in 0 .. ciphertext.len() {
+        let mut bad = ciphertext.clone();
+        bad[n] ^= 1;
+        assert_eq!(eax.decrypt(nonce, b"header", &bad, &tag),
+                   Err(AuthenticationError));
+        // a failed decryption must leave the data alone
+        let mut buf = bad.clone();
+        assert!(eax.decrypt_in_place_detached(nonce, b"header", &mut buf,
+                                              &tag).is_err());
+        assert_eq!(buf, bad);
+    }
+    for n in 0 .. TAGBYTES {
+        let mut bad = tag;
+        bad[n] ^= 0x80;
+        assert!(eax.decrypt(nonce, b"header", &ciphertext, &bad).is_err());
+    }
+    assert!(eax.decrypt(b"nonce!", b"header", &ciphertext, &tag).is_err());
+    assert!(eax.decrypt(b"", b"header", &ciphertext, &tag).is_err());
+    assert!(eax.decrypt(nonce, b"Header", &ciphertext, &tag).is_err());
+    // the nonce and associated data are kept apart
+    assert!(eax.decrypt(b"header", nonce, &ciphertext, &tag).is_err());
+    assert!(eax.decrypt(nonce, b"header", &ciphertext[1 ..], &tag).is_err());
+}
@@ -0,0 +1,189 @@
+//! [Galois/Counter Mode][1] (GCM), an authenticated encryption mode built on
+//! the Twofish primitive, as specified for AES in [NIST SP 800-38D][2].
+//!
+//! Only 96-bit (12-byte) nonces are supported, which is what nearly every
+//! GCM user uses anyway. The GHASH multiplication is done one bit at a time,
+//! with no secret-dependent branches or table lookups. That's slow compared
+//! to the table-driven or carry-less-multiply versions you'd find elsewhere,
+//! but it's constant time.
+//!
+//! [1]: https://en.wikipedia.org/wiki/Galois/Counter_Mode
+//! [2]: https://csrc.nist.gov/publications/detail/sp/800-38d/final
+
+use super::{Twofish, Ctr, BLOCKBYTES, AuthenticationError, cmac::ct_eq};
+
+/// The number of bytes in a GCM nonce.
+pub const NONCEBYTES: usize = 12;
+
+/// The number of bytes in a GCM tag.
+pub const TAGBYTES: usize = 16;
+
+/// The longest plaintext GCM allows in one message: 2^32 - 2 blocks. (This is
+/// also what keeps the 32-bit counter from wrapping.)
+const MAX_MESSAGE_BYTES: u64 = ((1 << 32) - 2) * BLOCKBYTES as u64;
+
+/// Multiply two elements of GF(2^128), in GCM's bit order (the first bit of
+/// the block is the coefficient of x^0). Constant time.
+fn gf_mul(x: u128, y: u128) -> u128 {
+    let mut z = 0;
+    let mut v = y;
+    for i in (0 .. 128).rev() {
+        z ^= v & 0u128.wrapping_sub((x >> i) & 1);
+        v = (v >> 1) ^ ((0xE1 << 120) & 0u128.wrapping_sub(v & 1));
+    }
+    z
+}
+
+/// Fold `data` into a GHASH state, zero-padding the last block.
+fn ghash_update(h: u128, y: &mut u128, data: &[u8]) {
+    for chunk in data.chunks(BLOCKBYTES) {
+        let mut block = [0; BLOCKBYTES];
+        block[.. chunk.len()].copy_from_slice(chunk);
+        *y = gf_mul(*y ^ u128::from_be_bytes(block), h);
+    }
+}
+
+/// The counter block for the given nonce and (32-bit) counter.
+fn counter_block(nonce: &[u8; NONCEBYTES], counter: u32) -> [u8; BLOCKBYTES] {
+    let mut block = [0; BLOCKBYTES];
+    block[.. NONCEBYTES].copy_from_slice(nonce);
+    block[NONCEBYTES ..].copy_from_slice(&counter.to_be_bytes());
+    block
+}
+
+/// Twofish in Galois/Counter Mode: encrypts with Twofish-CTR, and
+/// authenticates the ciphertext, along with any associated data, using GHASH
+/// keyed by a value derived from the Twofish key.
+///
+/// Never, ever use the same key and nonce to encrypt two different messages!
+/// With GCM, doing so doesn't just leak the messages; it lets an attacker
+/// forge new ones.
+///
+/// `TwofishGcm` isn't `Copy`, so with the `"zeroize"` feature it wipes its
+/// copy of the key schedule, and the GHASH key, when it's dropped.
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, TwofishGcm};
+/// let gcm = TwofishGcm::new(Twofish::new128(b"Sixteen byte key"));
+/// let nonce = [0u8; 12];
+/// let mut data = *b"Hi, Bob!";
+/// let tag = gcm.encrypt_in_place_detached(&nonce, b"To: Bob", &mut data);
+/// assert!(gcm.decrypt_in_place_detached(&nonce, b"To: Eve", &mut data,
+///                                       &tag).is_err());
+/// gcm.decrypt_in_place_detached(&nonce, b"To: Bob", &mut data, &tag)
+///     .unwrap();
+/// assert_eq!(&data, b"Hi, Bob!");
+/// ```
+#[derive(Clone)]
+pub struct TwofishGcm {
+    cipher: Twofish,
+    /// The hash subkey, the encryption of an all-zero block
+    h: u128,
+}
+
+impl TwofishGcm {
+    /// Set up GCM with the given cipher.
+    pub fn new(cipher: Twofish) -> TwofishGcm {
+        let mut h = [0; BLOCKBYTES];
+        cipher.encrypt(&[0; BLOCKBYTES], &mut h);
+        TwofishGcm { cipher, h: u128::from_be_bytes(h) }
+    }
+    /// Calculate the tag for the given nonce, associated data, and
+    /// ciphertext.
+    fn tag(&self, nonce: &[u8; NONCEBYTES], aad: &[u8], ciphertext: &[u8])
+           -> [u8; TAGBYTES] {
+        let mut y = 0;
+        ghash_update(self.h, &mut y, aad);
+        ghash_update(self.h, &mut y, ciphertext);
+        let lengths = ((aad.len() as u128 * 8) << 64)
+            | (ciphertext.len() as u128 * 8);
+        y = gf_mul(y ^ lengths, self.h);
+        let mut mask = [0; BLOCKBYTES];
+        self.cipher.encrypt(&counter_block(nonce, 1), &mut mask);
+        (y ^ u128::from_be_bytes(mask)).to_be_bytes()
+    }
+    /// Encrypt or decrypt `data` in place.
+    fn apply_keystream(&self, nonce: &[u8; NONCEBYTES], data: &mut [u8]) {
+        Ctr::new(&self.cipher, &counter_block(nonce, 2))
+            .apply_keystream(data);
+    }
+    /// Encrypt `data` in place, and return the tag that authenticates it
+    /// along with `aad` (associated data, which is authenticated but not
+    /// encrypted). `aad` may be empty.
+    ///
+    /// This is `encrypt` without the allocation: the ciphertext overwrites
+    /// the plaintext, and the tag is returned on its own, to be stored
+    /// wherever your format keeps it (a header, say). The ciphertext and tag
+    /// are exactly the ones `encrypt` would have returned.
+    ///
+    /// Panics if `data` is longer than GCM allows (a little under 64 GiB).
+    pub fn encrypt_in_place_detached(&self, nonce: &[u8; NONCEBYTES],
+                                     aad: &[u8], data: &mut [u8])
+                                     -> [u8; TAGBYTES] {
+        assert!(data.len() as u64 <= MAX_MESSAGE_BYTES,
+                "message is too long for GCM");
+        self.apply_keystream(nonce, data);
+        self.tag(nonce, aad, data)
+    }
+    /// Check the tag of `data` and `aad` in constant time, and, if it's
+    /// right, decrypt `data` in place. If the tag is wrong, `data` is left
+    /// alone and `AuthenticationError` is returned.
+    ///
+    /// The in-place counterpart of `decrypt`. It accepts the output of
+    /// either `encrypt` or `encrypt_in_place_detached`.
+    pub fn decrypt_in_place_detached(&self, nonce: &[u8; NONCEBYTES],
+                                     aad: &[u8], data: &mut [u8],
+                                     tag: &[u8; TAGBYTES])
+                                     -> Result<(), AuthenticationError> {
+        if data.len() as u64 > MAX_MESSAGE_BYTES
+            || !ct_eq(&self.tag(nonce, aad, data), tag) {
+            return Err(AuthenticationError)
+        }
+        self.apply_keystream(nonce, data);
+        Ok(())
+    }
+    /// Encrypt `plaintext`, returning the ciphertext (the same length as the
+    /// plaintext) and the tag that authenticates it along with `aad`.
+    /// Requires the `"alloc"` feature (enabled by `"std"`).
+    ///
+    /// Panics if `plaintext` is longer than GCM allows (a little under
+    /// 64 GiB).
+    #[cfg(feature="alloc")]
+    pub fn encrypt(&self, nonce: &[u8; NONCEBYTES], aad: &[u8],
+                   plaintext: &[u8])
+                   -> (alloc::vec::Vec<u8>, [u8; TAGBYTES]) {
+        let mut ciphertext = plaintext.to_vec();
+        let tag = self.encrypt_in_place_detached(nonce, aad,
+                                                 &mut ciphertext);
+        (ciphertext, tag)
+    }
+    /// Check the tag of `ciphertext` and `aad` in constant time, and, if
+    /// it's right, return the decrypted plaintext. Requires the `"alloc"`
+    /// feature (enabled by `"std"`).
+    #[cfg(feature="alloc")]
+    pub fn decrypt(&self, nonce: &[u8; NONCEBYTES], aad: &[u8],
+                   ciphertext: &[u8], tag: &[u8; TAGBYTES])
+                   -> Result<alloc::vec::Vec<u8>, AuthenticationError> {
+        let mut plaintext = ciphertext.to_vec();
+        self.decrypt_in_place_detached(nonce, aad, &mut plaintext, tag)?;
+        Ok(plaintext)
+    }
+}
+
+#[cfg(feature="zeroize")]
+impl Drop for TwofishGcm {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+        self.cipher.zeroize();
+        self.h.zeroize();
+    }
+}
+
+impl core::fmt::Debug for TwofishGcm {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "TwofishGcm {{ ... }}")
+    }
+}
+
+#[cfg(test)]
+mod tests;
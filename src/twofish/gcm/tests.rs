@@ -0,0 +1,112 @@
+use super::*;
+use crate::tests::unhex;
+
+#[test]
+pub fn gf_mul_identity() {
+    // in GCM's bit order, 1 is the top bit
+    let x = 0x66e94bd4ef8a2c3b884cfa59ca342b2eu128;
+    assert_eq!(gf_mul(x, 1 << 127), x);
+    assert_eq!(gf_mul(1 << 127, x), x);
+    assert_eq!(gf_mul(x, 0), 0);
+}
+
+/// Key, nonce, associated data, plaintext, ciphertext (hex), tag (hex).
+type Vector = (&'static [u8], [u8; NONCEBYTES], &'static [u8],
+               &'static [u8], &'static str, &'static str);
+
+#[test]
+pub fn known_vectors() {
+    // computed with an independent implementation of Twofish and GCM (the
+    // latter checked against a well-known AES-GCM)
+    let vectors: &[Vector] = &[
+        (&[0; 16], [0; 12], b"", b"",
+         "", "ca737ff1fd0fe5b8e41e90358a5f2cb1"),
+        (&[0; 16], [0; 12], b"", &[0; 16],
+         "f0fc7d86d814589a09d8ec136f95a124",
+         "601e88c6010d9479b9bbed6f0ebac515"),
+        (&[0xfe, 0xff, 0xe9, 0x92, 0x86, 0x65, 0x73, 0x1c,
+           0x6d, 0x6a, 0x8f, 0x94, 0x67, 0x30, 0x83, 0x08],
+         [0xca, 0xfe, 0xba, 0xbe, 0xfa, 0xce, 0xdb, 0xad,
+          0xde, 0xca, 0xf8, 0x88],
+         &[0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef,
+           0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef,
+           0xab, 0xad, 0xda, 0xd2],
+         &[0xd9, 0x31, 0x32, 0x25, 0xf8, 0x84, 0x06, 0xe5,
+           0xa5, 0x59, 0x09, 0xc5, 0xaf, 0xf5, 0x26, 0x9a,
+           0x86, 0xa7, 0xa9, 0x53, 0x15, 0x34, 0xf7, 0xda,
+           0x2e, 0x4c, 0x30, 0x3d, 0x8a, 0x31, 0x8a, 0x72,
+           0x1c, 0x3c, 0x0c, 0x95, 0x95, 0x68, 0x09, 0x53,
+           0x2f, 0xcf, 0x0e, 0x24, 0x49, 0xa6, 0xb5, 0x25,
+           0xb1, 0x6a, 0xed, 0xf5, 0xaa, 0x0d, 0xe6, 0x57,
+           0xba, 0x63, 0x7b, 0x39],
+         "d98a3cad8adeced9f76bf0ec859db8777812db80b98461c826001c7b2230aaed\
+          2a8cacf8479fe0976b6810ab3ac14783242eb78d526b7db0afeffed3",
+         "28905957ca1e450c83fb6af0464ace1e"),
+        (&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+           16, 17, 18, 19, 20, 21, 22, 23],
+         [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11], b"Header only", b"",
+         "", "49338d6abef4557614f236d391de8ddc"),
+        (&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+           16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31],
+         [16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27], b"",
+         b"The quick brown fox jumps over the lazy dog",
+         "d3202192a2d731eb3abf126d1d4454816b2c93e7ae7174277295293089cc9ae8\
+          2f6986f9b6447d9103018d",
+         "765453791b9c3c1f765847ba94fb3fb3"),
+    ];
+    for (key, nonce, aad, plaintext, ciphertext, tag) in vectors {
+        let gcm = TwofishGcm::new(Twofish::new(key).unwrap());
+        let mut buf = plaintext.to_vec();
+        let t = gcm.encrypt_in_place_detached(nonce, aad, &mut buf);
+        assert_eq!(buf, unhex(ciphertext));
+        assert_eq!(t[..], unhex(tag)[..]);
+        gcm.decrypt_in_place_detached(nonce, aad, &mut buf, &t).unwrap();
+        assert_eq!(buf, *plaintext);
+    }
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn gcm_round_trip() {
+    let gcm = TwofishGcm::new(Twofish::new192(&[0x5A; 24]));
+    let data: Vec<u8> = (0 .. 100).collect();
+    for len in 0 .. data.len() {
+        let nonce = [len as u8; NONCEBYTES];
+        let aad = &data[.. len / 3];
+        let mut buf = data[.. len].to_vec();
+        let tag = gcm.encrypt_in_place_detached(&nonce, aad, &mut buf);
+        assert_eq!(gcm.encrypt(&nonce, aad, &data[.. len]), (buf.clone(), tag));
+        gcm.decrypt_in_place_detached(&nonce, aad, &mut buf, &tag).unwrap();
+        assert_eq!(buf, &data[.. len]);
+    }
+}
+
+#[test]
+#[cfg(feature="alloc")]
+pub fn gcm_tampering() {
+    let gcm = TwofishGcm::new(Twofish::new128(&[0x11; 16]));
+    let nonce = [0x22; NONCEBYTES];
+    let (ciphertext, tag) = gcm.encrypt(&nonce, b"header", b"Meet at noon.");
+    for n in 0 .. ciphertext.len() {
+        let mut bad = ciphertext.clone();
+        bad[n] ^= 1;
+        assert_eq!(gcm.decrypt(&nonce, b"header", &bad, &tag),
+                   Err(AuthenticationError));
+        // a failed decryption must leave the data alone
+        let mut buf = bad.clone();
+        assert!(gcm.decrypt_in_place_detached(&nonce, b"header", &mut buf,
+                                              &tag).is_err());
+        assert_eq!(buf, bad);
+    }
+    for n in 0 .. TAGBYTES {
+        let mut bad = tag;
+        bad[n] ^= 0x80;
+        assert!(gcm.decrypt(&nonce, b"header", &ciphertext, &bad).is_err());
+    }
+    let mut bad_nonce = nonce;
+    bad_nonce[NONCEBYTES - 1] ^= 1;
+    assert!(gcm.decrypt(&bad_nonce, b"header", &ciphertext, &tag).is_err());
+    assert!(gcm.decrypt(&nonce, b"Header", &ciphertext, &tag).is_err());
+    assert!(gcm.decrypt(&nonce, b"", &ciphertext, &tag).is_err());
+    assert!(gcm.decrypt(&nonce, b"header", &ciphertext[1 ..], &tag).is_err());
+}
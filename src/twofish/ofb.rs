@@ -0,0 +1,116 @@
+//! [Output feedback][1] (OFB) mode, built on the Twofish primitive.
+//!
+//! [1]: https://en.wikipedia.org/wiki/Block_cipher_mode_of_operation#Output_feedback_(OFB)
+
+use super::{Twofish, BLOCKBYTES};
+
+/// A Twofish-OFB keystream. The feedback register is repeatedly encrypted,
+/// and each result is both the next block of keystream and the next value of
+/// the register. The keystream doesn't depend on the data at all, so
+/// encryption and decryption are the same operation: XOR the keystream into
+/// the data.
+///
+/// Never, ever use the same key and IV to encrypt two different messages!
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, Ofb};
+/// let twofish = Twofish::new128(b"Sixteen byte key");
+/// let iv = [0u8; 16];
+/// let mut data = *b"Attack at dawn!! Or maybe a little after.";
+/// Ofb::new(&twofish, &iv).apply_keystream(&mut data[..]);
+/// assert_ne!(&data[..], b"Attack at dawn!! Or maybe a little after.");
+/// Ofb::new(&twofish, &iv).apply_keystream(&mut data[..]);
+/// assert_eq!(&data[..], b"Attack at dawn!! Or maybe a little after.");
+/// ```
+#[derive(Clone)]
+pub struct Ofb<'a> {
+    cipher: &'a Twofish,
+    register: [u8; BLOCKBYTES],
+    pos: usize,
+}
+
+impl<'a> Ofb<'a> {
+    /// Set up OFB mode with the given cipher and IV.
+    pub fn new(cipher: &'a Twofish, iv: &[u8; BLOCKBYTES]) -> Ofb<'a> {
+        Ofb { cipher, register: *iv, pos: BLOCKBYTES }
+    }
+    /// Start over with a new IV, as if this were a freshly constructed `Ofb`
+    /// using the same cipher. Any leftover keystream is discarded.
+    pub fn reset_iv(&mut self, iv: &[u8; BLOCKBYTES]) {
+        self.register = *iv;
+        self.pos = BLOCKBYTES;
+    }
+    /// XOR the next `data.len()` bytes of keystream into `data`. Data need not
+    /// be provided in whole blocks; the leftover keystream from one call is
+    /// used by the next.
+    pub fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            if self.pos == BLOCKBYTES {
+                let register = self.register;
+                self.cipher.encrypt(&register, &mut self.register);
+                self.pos = 0;
+            }
+            *byte ^= self.register[self.pos];
+            self.pos += 1;
+        }
+    }
+}
+
+impl core::fmt::Debug for Ofb<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Ofb {{ ... }}")
+    }
+}
+
+struct OfbStream {
+    cipher: Twofish,
+    register: [u8; BLOCKBYTES],
+    pos: usize,
+}
+
+impl Iterator for OfbStream {
+    type Item = u8;
+    fn next(&mut self) -> Option<u8> {
+        if self.pos == BLOCKBYTES {
+            let mut next = [0u8; BLOCKBYTES];
+            self.cipher.encrypt(&self.register, &mut next);
+            self.register = next;
+            self.pos = 0;
+        }
+        let ret = self.register[self.pos];
+        self.pos += 1;
+        Some(ret)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Produce an endless OFB keystream from the given key and IV. This is
+/// intended for generating large, reproducible, pseudo-random test data,
+/// e.g. `ofb_stream(key, iv).take(n).collect()`. Panics if the key is not
+/// 16, 24, or 32 bytes long.
+///
+/// Since Twofish is a permutation, the keystream is purely periodic: it will
+/// eventually return to the IV and repeat itself from the beginning. The
+/// length of that cycle depends on the key and IV, but for any reasonable key
+/// you should expect it to be on the order of 2^127 blocks. You will not live
+/// to see it repeat.
+///
+/// ```rust
+/// # use lsx::twofish;
+/// let fixture: Vec<u8> = twofish::ofb_stream(b"my fixture seed!", &[0; 16])
+///     .take(1000).collect();
+/// let again: Vec<u8> = twofish::ofb_stream(b"my fixture seed!", &[0; 16])
+///     .take(1000).collect();
+/// assert_eq!(fixture, again);
+/// ```
+pub fn ofb_stream(key: &[u8], iv: &[u8; BLOCKBYTES])
+    -> impl Iterator<Item = u8> {
+    let cipher = Twofish::new(key)
+        .expect("Twofish keys must be 16, 24, or 32 bytes long");
+    OfbStream { cipher, register: *iv, pos: BLOCKBYTES }
+}
+
+#[cfg(test)]
+mod tests;
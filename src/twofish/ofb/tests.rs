@@ -0,0 +1,79 @@
+use super::*;
+
+#[test]
+pub fn ofb_stream_feedback() {
+    let key = [0x5Au8; 24];
+    let iv = [0x01u8; BLOCKBYTES];
+    let cipher = Twofish::new192(&key);
+    let mut expected = [0u8; BLOCKBYTES * 3];
+    let mut register = iv;
+    for chunk in expected.chunks_exact_mut(BLOCKBYTES) {
+        let mut next = [0u8; BLOCKBYTES];
+        cipher.encrypt(&register, &mut next);
+        chunk.copy_from_slice(&next[..]);
+        register = next;
+    }
+    let stream: Vec<u8> = ofb_stream(&key[..], &iv).take(expected.len())
+        .collect();
+    assert_eq!(&stream[..], &expected[..]);
+}
+
+#[test]
+#[should_panic]
+pub fn ofb_stream_bad_key() {
+    let _ = ofb_stream(&[0u8; 17], &[0u8; BLOCKBYTES]);
+}
+
+#[test]
+pub fn ofb_matches_stream() {
+    let key = [0x5Au8; 24];
+    let iv = [0x01u8; BLOCKBYTES];
+    let cipher = Twofish::new192(&key);
+    let stream: Vec<u8> = ofb_stream(&key[..], &iv).take(BLOCKBYTES * 4)
+        .collect();
+    for chunksize in &[1, 5, 16, 17, 64] {
+        let mut data = [0u8; BLOCKBYTES * 4];
+        let mut ofb = Ofb::new(&cipher, &iv);
+        for chunk in data.chunks_mut(*chunksize) {
+            ofb.apply_keystream(chunk);
+        }
+        assert_eq!(&data[..], &stream[..]);
+    }
+}
+
+#[test]
+pub fn ofb_independent_instances() {
+    let cipher = Twofish::new128(&[0x2D; 16]);
+    let iv = [0x77; BLOCKBYTES];
+    let mut a = Ofb::new(&cipher, &iv);
+    let mut b = Ofb::new(&cipher, &iv);
+    let mut keystream_a = [0u8; 100];
+    let mut keystream_b = [0u8; 100];
+    a.apply_keystream(&mut keystream_a[.. 30]);
+    b.apply_keystream(&mut keystream_b[.. 7]);
+    a.apply_keystream(&mut keystream_a[30 ..]);
+    b.apply_keystream(&mut keystream_b[7 ..]);
+    assert_eq!(&keystream_a[..], &keystream_b[..]);
+    // and since it's just XOR, it undoes itself
+    let plaintext = *b"It's the same both ways.";
+    let mut data = plaintext;
+    Ofb::new(&cipher, &iv).apply_keystream(&mut data[..]);
+    for ((d, p), k) in data.iter().zip(&plaintext).zip(&keystream_a[..]) {
+        assert_eq!(*d, p ^ k);
+    }
+    Ofb::new(&cipher, &iv).apply_keystream(&mut data[..]);
+    assert_eq!(data, plaintext);
+}
+
+#[test]
+pub fn ofb_reset_iv() {
+    let cipher = Twofish::new128(&[0x5A; 16]);
+    let mut ofb = Ofb::new(&cipher, &[1; BLOCKBYTES]);
+    ofb.apply_keystream(&mut [0u8; 21][..]);
+    ofb.reset_iv(&[2; BLOCKBYTES]);
+    let mut reset = [0u8; 40];
+    ofb.apply_keystream(&mut reset[..]);
+    let mut fresh = [0u8; 40];
+    Ofb::new(&cipher, &[2; BLOCKBYTES]).apply_keystream(&mut fresh[..]);
+    assert_eq!(reset, fresh);
+}
@@ -1,4 +1,5 @@
 use super::*;
+use core::convert::TryFrom;
 
 #[derive(Debug,PartialEq,Copy,Clone)]
 enum Key {
@@ -1059,3 +1060,319 @@ pub fn ecb_tbl() {
     }
     if !ok { panic!("Not okay! (see stdout)") }
 }
+
+#[test]
+#[cfg(feature="getrandom")]
+pub fn random_iv_differs() {
+    // 2^-128 chance of a spurious failure. I'll take those odds.
+    assert_ne!(random_iv(), random_iv());
+}
+
+fn _assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+pub fn send_sync() {
+    _assert_send_sync::<Twofish>();
+    let twofish = Twofish::new128(&[0x42; 16]);
+    let mut expected = [0u8; BLOCKBYTES];
+    twofish.encrypt(&[0; BLOCKBYTES], &mut expected);
+    std::thread::scope(|scope| {
+        for _ in 0 .. 4 {
+            scope.spawn(|| {
+                let mut ciphertext = [0u8; BLOCKBYTES];
+                twofish.encrypt(&[0; BLOCKBYTES], &mut ciphertext);
+                assert_eq!(ciphertext, expected);
+            });
+        }
+    });
+}
+
+#[test]
+pub fn encrypt_bulk() {
+    let twofish = Twofish::new256(&[0x24; 32]);
+    let mut data: Vec<u8> = (0 .. BLOCKBYTES as u8 * 5).collect();
+    let mut expected = data.clone();
+    for block in expected.chunks_exact_mut(BLOCKBYTES) {
+        let input = *array_ref!(block, 0, BLOCKBYTES);
+        twofish.encrypt(&input, array_mut_ref!(block, 0, BLOCKBYTES));
+    }
+    twofish.encrypt_bulk(&mut data[..]);
+    assert_eq!(data, expected);
+}
+
+#[test]
+pub fn new_sized_matches() {
+    fn check(a: Twofish, b: Twofish) {
+        let mut x = [0u8; BLOCKBYTES];
+        let mut y = [0u8; BLOCKBYTES];
+        a.encrypt(&[0x6D; BLOCKBYTES], &mut x);
+        b.encrypt(&[0x6D; BLOCKBYTES], &mut y);
+        assert_eq!(x, y);
+    }
+    check(Twofish::new_sized(&[0x11; 16]), Twofish::new128(&[0x11; 16]));
+    check(Twofish::new_sized(&[0x22; 24]), Twofish::new192(&[0x22; 24]));
+    check(Twofish::new_sized(&[0x33; 32]), Twofish::new256(&[0x33; 32]));
+}
+
+#[test]
+#[cfg(feature="bench")]
+pub fn build_sboxes_matches() {
+    assert!(Twofish::build_sboxes(&[0x11; 16])[..]
+            == Twofish::new128(&[0x11; 16]).s[..]);
+    assert!(Twofish::build_sboxes(&[0x22; 24])[..]
+            == Twofish::new192(&[0x22; 24]).s[..]);
+    assert!(Twofish::build_sboxes(&[0x33; 32])[..]
+            == Twofish::new256(&[0x33; 32]).s[..]);
+}
+
+#[test]
+pub fn twofish_eq() {
+    assert!(Twofish::new128(&[0x44; 16]) == Twofish::new128(&[0x44; 16]));
+    assert!(Twofish::new256(&[0x44; 32]) == Twofish::new256(&[0x44; 32]));
+    let mut key = [0x44; 16];
+    key[15] ^= 1;
+    assert!(Twofish::new128(&key) != Twofish::new128(&[0x44; 16]));
+    assert!(Twofish::new192(&[0x44; 24]) != Twofish::new128(&[0x44; 16]));
+    assert!(Twofish::new256(&[0x44; 32]) != Twofish::new192(&[0x44; 24]));
+}
+
+#[test]
+pub fn new256_xor() {
+    let share_a: [u8; 32] = std::array::from_fn(|i| (i * 37) as u8);
+    let share_b = [0xA5; 32];
+    let key: [u8; 32] = std::array::from_fn(|i| share_a[i] ^ share_b[i]);
+    let mut expected = [0u8; BLOCKBYTES];
+    let mut actual = [0u8; BLOCKBYTES];
+    Twofish::new256(&key).encrypt(&[0x11; BLOCKBYTES], &mut expected);
+    Twofish::new256_xor(&share_a, &share_b)
+        .encrypt(&[0x11; BLOCKBYTES], &mut actual);
+    assert_eq!(actual, expected);
+}
+
+#[test]
+pub fn encrypt_into2() {
+    let twofish = Twofish::new128(&[0x77; 16]);
+    let plaintext = [0x88; BLOCKBYTES];
+    let mut expected = [0u8; BLOCKBYTES];
+    twofish.encrypt(&plaintext, &mut expected);
+    for split in 0 ..= BLOCKBYTES {
+        // the block wraps around the end of a block-sized ring buffer
+        let mut ring = [0u8; BLOCKBYTES];
+        let (out_b, out_a) = ring.split_at_mut(BLOCKBYTES - split);
+        twofish.encrypt_into2(&plaintext, out_a, out_b);
+        assert_eq!(ring[BLOCKBYTES - split ..], expected[.. split]);
+        assert_eq!(ring[.. BLOCKBYTES - split], expected[split ..]);
+    }
+    // extra room in either slice is left alone
+    let mut out_a = [0u8; 10];
+    let mut out_b = [0u8; 10];
+    twofish.encrypt_into2(&plaintext, &mut out_a, &mut out_b);
+    assert_eq!(out_a[..], expected[.. 10]);
+    assert_eq!(out_b[.. 6], expected[10 ..]);
+    assert_eq!(out_b[6 ..], [0; 4]);
+}
+
+#[test]
+pub fn encrypt_4blocks() {
+    for twofish in &[Twofish::new128(&[0x01; 16]),
+                     Twofish::new192(&[0x02; 24]),
+                     Twofish::new256(&[0x03; 32])] {
+        let mut input = [0u8; 64];
+        for (n, x) in input.iter_mut().enumerate() { *x = (n * 37) as u8 }
+        let mut expected = [0u8; 64];
+        for (i, o) in input.chunks_exact(BLOCKBYTES)
+            .zip(expected.chunks_exact_mut(BLOCKBYTES)) {
+            twofish.encrypt(array_ref!(i, 0, BLOCKBYTES),
+                            array_mut_ref!(o, 0, BLOCKBYTES));
+        }
+        let mut out = [0u8; 64];
+        twofish.encrypt_4blocks(&input, &mut out);
+        assert_eq!(out[..], expected[..]);
+        let mut back = [0u8; 64];
+        twofish.decrypt_4blocks(&out, &mut back);
+        assert_eq!(back[..], input[..]);
+    }
+}
+
+#[test]
+pub fn encrypt_blocks() {
+    let twofish = Twofish::new192(&[0x04; 24]);
+    for blocks in 0 .. 11 {
+        let plaintext: Vec<u8> = (0 .. blocks * BLOCKBYTES)
+            .map(|n| (n * 29) as u8).collect();
+        let mut expected = plaintext.clone();
+        for block in expected.chunks_exact_mut(BLOCKBYTES) {
+            let input = *array_ref!(block, 0, BLOCKBYTES);
+            twofish.encrypt(&input, array_mut_ref!(block, 0, BLOCKBYTES));
+        }
+        let mut data = plaintext.clone();
+        twofish.encrypt_blocks(&mut data);
+        assert_eq!(data, expected);
+        twofish.decrypt_blocks(&mut data);
+        assert_eq!(data, plaintext);
+    }
+}
+
+#[test]
+pub fn in_place() {
+    for twofish in &[Twofish::new128(&[0x06; 16]),
+                     Twofish::new256(&[0x07; 32])] {
+        let plaintext = *b"Sixteen bytes!!!";
+        let mut ciphertext = [0u8; BLOCKBYTES];
+        twofish.encrypt(&plaintext, &mut ciphertext);
+        let mut block = plaintext;
+        twofish.encrypt_in_place(&mut block);
+        assert_eq!(block, ciphertext);
+        twofish.decrypt_in_place(&mut block);
+        assert_eq!(block, plaintext);
+    }
+}
+
+#[test]
+#[should_panic]
+pub fn encrypt_blocks_partial() {
+    Twofish::new128(&[0x05; 16]).encrypt_blocks(&mut [0; BLOCKBYTES + 1]);
+}
+
+#[test]
+pub fn new_from_slice() {
+    let key: Vec<u8> = (0 .. 32).map(|n| (n * 11) as u8).collect();
+    let mut expected = [0u8; BLOCKBYTES];
+    let mut got = [0u8; BLOCKBYTES];
+    Twofish::new128(array_ref!(key, 0, 16)).encrypt(&[0; 16], &mut expected);
+    Twofish::new(&key[.. 16]).unwrap().encrypt(&[0; 16], &mut got);
+    assert_eq!(got, expected);
+    Twofish::new192(array_ref!(key, 0, 24)).encrypt(&[0; 16], &mut expected);
+    Twofish::new(&key[.. 24]).unwrap().encrypt(&[0; 16], &mut got);
+    assert_eq!(got, expected);
+    Twofish::new256(array_ref!(key, 0, 32)).encrypt(&[0; 16], &mut expected);
+    Twofish::new(&key[..]).unwrap().encrypt(&[0; 16], &mut got);
+    assert_eq!(got, expected);
+    for &len in &[0, 15, 17, 31, 33] {
+        assert_eq!(Twofish::new(&[0; 40][.. len]).unwrap_err(),
+                   KeyLengthError { len });
+        assert_eq!(Twofish::try_from(&[0; 40][.. len]).unwrap_err(),
+                   KeyLengthError { len });
+    }
+    Twofish::try_from(&key[.. 24]).unwrap().encrypt(&[0; 16], &mut got);
+    Twofish::new192(array_ref!(key, 0, 24)).encrypt(&[0; 16], &mut expected);
+    assert_eq!(got, expected);
+}
+
+#[test]
+#[cfg(feature="cipher")]
+pub fn cipher_traits() {
+    use cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+    assert_eq!(<Twofish as BlockSizeUser>::block_size(), BLOCKBYTES);
+    let key: Vec<u8> = (0 .. 32).map(|n| (n * 5) as u8).collect();
+    let generic = <Twofish as KeyInit>::new(key[..].into());
+    let native = Twofish::new256(array_ref!(key, 0, 32));
+    let mut blocks = [[0u8; BLOCKBYTES].into(); 5];
+    for (n, block) in blocks.iter_mut().enumerate() {
+        *block = [n as u8; BLOCKBYTES].into();
+    }
+    let plaintext = blocks;
+    BlockEncrypt::encrypt_blocks(&generic, &mut blocks);
+    for (c, p) in blocks.iter().zip(plaintext.iter()) {
+        let mut expected = [0u8; BLOCKBYTES];
+        native.encrypt(array_ref!(p, 0, BLOCKBYTES), &mut expected);
+        assert_eq!(c[..], expected[..]);
+    }
+    BlockDecrypt::decrypt_blocks(&generic, &mut blocks);
+    assert_eq!(blocks, plaintext);
+    for &len in &[16, 24, 32] {
+        let generic = Twofish::new_from_slice(&key[.. len]).unwrap();
+        let native = Twofish::new(&key[.. len]).unwrap();
+        let mut a = [0x99u8; BLOCKBYTES];
+        let mut b = a;
+        generic.encrypt_block((&mut a).into());
+        native.encrypt_in_place(&mut b);
+        assert_eq!(a, b);
+    }
+    assert!(Twofish::new_from_slice(&key[.. 20]).is_err());
+}
+
+#[test]
+#[cfg(feature="cipher")]
+pub fn cipher_block_cipher() {
+    use cipher::{BlockCipher, BlockDecrypt, BlockEncrypt, KeyInit};
+    fn f<C: BlockCipher + BlockEncrypt + BlockDecrypt + KeyInit>() {}
+    f::<Twofish>();
+}
+
+#[test]
+pub fn key_quality() {
+    assert_eq!(Twofish::key_quality(&[0; 16]), KeyQuality::AllZero);
+    assert_eq!(Twofish::key_quality(&[0; 32]), KeyQuality::AllZero);
+    assert_eq!(Twofish::key_quality(&[]), KeyQuality::AllZero);
+    assert_eq!(Twofish::key_quality(&[0xFF; 24]), KeyQuality::AllSame);
+    assert_eq!(Twofish::key_quality(b"aaaaaaaaaaaaaaaa"), KeyQuality::AllSame);
+    let ascending: Vec<u8> = (0 .. 32).collect();
+    assert_eq!(Twofish::key_quality(&ascending), KeyQuality::Sequential);
+    let wrapping: Vec<u8> = (0xF0 ..= 0xFF).chain(0 .. 16).collect();
+    assert_eq!(Twofish::key_quality(&wrapping), KeyQuality::Sequential);
+    let descending: Vec<u8> = (0 .. 16).rev().collect();
+    assert_eq!(Twofish::key_quality(&descending), KeyQuality::Sequential);
+    let mut almost = ascending.clone();
+    almost[31] = 0;
+    assert_eq!(Twofish::key_quality(&almost), KeyQuality::Ok);
+    let mut almost = [0u8; 16];
+    almost[7] = 1;
+    assert_eq!(Twofish::key_quality(&almost), KeyQuality::Ok);
+    assert_eq!(Twofish::key_quality(b"Sixteen byte key"), KeyQuality::Ok);
+}
+
+#[cfg(feature="zeroize")]
+std::thread_local! {
+    /// Whether the last `ZeroizingTwofish` dropped on this thread was left
+    /// all zeroes.
+    static DROPPED_WIPED: std::cell::Cell<Option<bool>>
+        = const { std::cell::Cell::new(None) };
+}
+
+/// Called by `ZeroizingTwofish`'s `Drop`, after wiping.
+#[cfg(feature="zeroize")]
+pub fn note_drop(twofish: &Twofish) {
+    let wiped = twofish.s.iter().flatten()
+        .chain(twofish.w.iter()).chain(twofish.k.iter()).all(|&x| x == 0);
+    DROPPED_WIPED.with(|x| x.set(Some(wiped)));
+}
+
+#[test]
+#[cfg(feature="zeroize")]
+pub fn zeroizing_twofish() {
+    fn check<T: zeroize::ZeroizeOnDrop>() {}
+    check::<ZeroizingTwofish>();
+    let key = b"Sixteen byte key";
+    let twofish = ZeroizingTwofish::new(key).unwrap();
+    assert_eq!(*twofish, Twofish::new128(key));
+    assert!(ZeroizingTwofish::new(&key[.. 15]).is_err());
+    DROPPED_WIPED.with(|x| x.set(None));
+    drop(twofish);
+    assert_eq!(DROPPED_WIPED.with(|x| x.get()), Some(true));
+    let copy = Twofish::new128(key);
+    drop(ZeroizingTwofish::from(copy));
+    assert_eq!(DROPPED_WIPED.with(|x| x.get()), Some(true));
+    // (the original is untouched)
+    assert_eq!(copy, Twofish::new128(key));
+}
+
+#[test]
+#[cfg(feature="zeroize")]
+pub fn zeroize() {
+    use zeroize::Zeroize;
+    fn check<T: Copy + Zeroize>() {}
+    check::<Twofish>();
+    let mut twofish = Twofish::new128(b"Sixteen byte key");
+    twofish.zeroize();
+    assert!(twofish.s.iter().flatten().all(|&x| x == 0));
+    assert!(twofish.w.iter().all(|&x| x == 0));
+    assert!(twofish.k.iter().all(|&x| x == 0));
+}
+
+#[test]
+pub fn associated_sizes() {
+    let twofish = Twofish::new128(&[0; 16]);
+    assert_eq!(Twofish::BLOCK_LEN, BLOCKBYTES);
+    assert_eq!(Twofish::BLOCK_LEN, twofish.block_size());
+}
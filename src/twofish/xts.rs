@@ -0,0 +1,136 @@
+//! [XTS][1] mode (XEX-based tweaked-codebook mode with ciphertext stealing),
+//! built on the Twofish primitive, as specified for AES in [IEEE 1619][2].
+//!
+//! XTS is meant for disk encryption. Each sector is encrypted in place,
+//! independently of the others, with a tweak derived from its index, so
+//! there's no IV to store and the ciphertext is no bigger than the
+//! plaintext. The price is that it isn't authenticated, and that the same
+//! data written to the same sector always encrypts the same way.
+//!
+//! [1]: https://en.wikipedia.org/wiki/Disk_encryption_theory#XTS
+//! [2]: https://en.wikipedia.org/wiki/IEEE_P1619
+
+use super::{Twofish, BLOCKBYTES};
+
+/// Multiply a tweak by x in GF(2^128), to get the tweak for the next block.
+/// Unlike CMAC and GCM, XTS treats the block as a little-endian number.
+fn mul_alpha(tweak: &[u8; BLOCKBYTES]) -> [u8; BLOCKBYTES] {
+    let x = u128::from_le_bytes(*tweak);
+    // constant time: 0x87 if the top bit was set, 0 otherwise
+    ((x << 1) ^ (0x87 & 0u128.wrapping_sub(x >> 127))).to_le_bytes()
+}
+
+fn xor_in(block: &mut [u8; BLOCKBYTES], tweak: &[u8; BLOCKBYTES]) {
+    for (b, t) in block.iter_mut().zip(tweak.iter()) {
+        *b ^= t;
+    }
+}
+
+/// Twofish-XTS, for encrypting disk sectors (or anything else that's split
+/// into numbered, fixed-size units). Uses two independent keys: one for the
+/// data, and one for the tweaks. Per IEEE 1619, they must not be the same
+/// key.
+///
+/// Sectors can be any length of at least one block. Ones that aren't a
+/// multiple of `BLOCKBYTES` use ciphertext stealing, so they still come out
+/// the same length.
+///
+/// ```rust
+/// # use lsx::twofish::{Twofish, Xts};
+/// let data_key = Twofish::new256(&[1; 32]);
+/// let tweak_key = Twofish::new256(&[2; 32]);
+/// let xts = Xts::new(&data_key, &tweak_key);
+/// let mut sector = [0x42u8; 512];
+/// xts.encrypt_sector(1234, &mut sector);
+/// assert_ne!(&sector[..], &[0x42u8; 512][..]);
+/// xts.decrypt_sector(1234, &mut sector);
+/// assert_eq!(&sector[..], &[0x42u8; 512][..]);
+/// ```
+#[derive(Clone,Copy)]
+pub struct Xts<'a> {
+    cipher: &'a Twofish,
+    tweak_cipher: &'a Twofish,
+}
+
+impl<'a> Xts<'a> {
+    /// Set up XTS mode with the given data cipher and tweak cipher.
+    pub fn new(cipher: &'a Twofish, tweak_cipher: &'a Twofish) -> Xts<'a> {
+        Xts { cipher, tweak_cipher }
+    }
+    /// The tweak for the first block of the given sector.
+    fn initial_tweak(&self, sector_index: u128) -> [u8; BLOCKBYTES] {
+        let mut tweak = [0; BLOCKBYTES];
+        self.tweak_cipher.encrypt(&sector_index.to_le_bytes(), &mut tweak);
+        tweak
+    }
+    fn encrypt_block(&self, block: &mut [u8; BLOCKBYTES],
+                     tweak: &[u8; BLOCKBYTES]) {
+        xor_in(block, tweak);
+        self.cipher.encrypt_in_place(block);
+        xor_in(block, tweak);
+    }
+    fn decrypt_block(&self, block: &mut [u8; BLOCKBYTES],
+                     tweak: &[u8; BLOCKBYTES]) {
+        xor_in(block, tweak);
+        self.cipher.decrypt_in_place(block);
+        xor_in(block, tweak);
+    }
+    /// Split a sector into the blocks that are handled normally, and (if the
+    /// sector isn't a whole number of blocks) the last whole block and the
+    /// partial block after it, which are handled with ciphertext stealing.
+    fn split(data: &mut [u8]) -> (&mut [u8], &mut [u8]) {
+        assert!(data.len() >= BLOCKBYTES,
+                "XTS sectors must be at least one block long");
+        let tail = data.len() % BLOCKBYTES;
+        let normal = if tail == 0 { data.len() }
+        else { data.len() - tail - BLOCKBYTES };
+        data.split_at_mut(normal)
+    }
+    /// Encrypt the sector with the given index, in place.
+    ///
+    /// Panics if `data` is shorter than `BLOCKBYTES`.
+    pub fn encrypt_sector(&self, sector_index: u128, data: &mut [u8]) {
+        let (body, stolen) = Xts::split(data);
+        let mut tweak = self.initial_tweak(sector_index);
+        for block in body.chunks_exact_mut(BLOCKBYTES) {
+            self.encrypt_block(array_mut_ref!(block, 0, BLOCKBYTES), &tweak);
+            tweak = mul_alpha(&tweak);
+        }
+        if stolen.is_empty() { return }
+        let (last, partial) = stolen.split_at_mut(BLOCKBYTES);
+        let last = array_mut_ref!(last, 0, BLOCKBYTES);
+        self.encrypt_block(last, &tweak);
+        // The start of that ciphertext becomes the partial block, and the
+        // partial plaintext takes its place, to be encrypted again.
+        last[.. partial.len()].swap_with_slice(partial);
+        self.encrypt_block(last, &mul_alpha(&tweak));
+    }
+    /// Decrypt the sector with the given index, in place.
+    ///
+    /// Panics if `data` is shorter than `BLOCKBYTES`.
+    pub fn decrypt_sector(&self, sector_index: u128, data: &mut [u8]) {
+        let (body, stolen) = Xts::split(data);
+        let mut tweak = self.initial_tweak(sector_index);
+        for block in body.chunks_exact_mut(BLOCKBYTES) {
+            self.decrypt_block(array_mut_ref!(block, 0, BLOCKBYTES), &tweak);
+            tweak = mul_alpha(&tweak);
+        }
+        if stolen.is_empty() { return }
+        let (last, partial) = stolen.split_at_mut(BLOCKBYTES);
+        let last = array_mut_ref!(last, 0, BLOCKBYTES);
+        // the reverse of encrypt_sector: the last whole block was encrypted
+        // with the later tweak
+        self.decrypt_block(last, &mul_alpha(&tweak));
+        last[.. partial.len()].swap_with_slice(partial);
+        self.decrypt_block(last, &tweak);
+    }
+}
+
+impl core::fmt::Debug for Xts<'_> {
+    fn fmt(&self, fmt: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(fmt, "Xts {{ ... }}")
+    }
+}
+
+#[cfg(test)]
+mod tests;